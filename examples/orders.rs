@@ -81,6 +81,8 @@ fn main() {
                 },
                 delay_time: 0,
                 twitch_id: 123456789,
+                fixed_allocation: 0,
+                on_overflow: false,
             },
         );
         println!("{:#?}", res);
@@ -100,6 +102,8 @@ fn main() {
                 channel_url: String::from(
                     "https://www.youtube.com/channel/UCtI0Hodo5o5dUb67FeUjDeA",
                 ),
+                fixed_allocation: 0,
+                on_overflow: false,
             },
         );
         println!("{:#?}", res);
@@ -2,15 +2,22 @@ use std::error::Error;
 
 use crate::{
     client::Requests,
+    lifecycle::{self, Transition},
     model::{order::LaunchParams, result::ActionResult, task::TaskStatus},
 };
 
 fn patch(
     c: &impl Requests,
+    order_id: u32,
+    transition: Transition,
     path: &str,
     payload: Option<String>,
 ) -> Result<ActionResult, Box<dyn Error>> {
+    lifecycle::ensure(c, order_id, transition)?;
     let resp = c.patch(path, payload)?;
+    if let Some(cache) = c.order_cache() {
+        cache.remove(order_id);
+    }
     let res: ActionResult = serde_json::from_str(&resp)?;
     Ok(res)
 }
@@ -32,7 +39,13 @@ fn patch(
 /// }
 /// ```
 pub fn run(c: &impl Requests, order_id: u32) -> Result<ActionResult, Box<dyn Error>> {
-    self::patch(c, &format!("/orders/{}/action/run/", order_id), None)
+    self::patch(
+        c,
+        order_id,
+        Transition::Run,
+        &format!("/orders/{}/action/run/", order_id),
+        None,
+    )
 }
 
 /// Stop order
@@ -52,7 +65,13 @@ pub fn run(c: &impl Requests, order_id: u32) -> Result<ActionResult, Box<dyn Err
 /// }
 /// ```
 pub fn stop(c: &impl Requests, order_id: u32) -> Result<ActionResult, Box<dyn Error>> {
-    self::patch(c, &format!("/orders/{}/action/stop/", order_id), None)
+    self::patch(
+        c,
+        order_id,
+        Transition::Stop,
+        &format!("/orders/{}/action/stop/", order_id),
+        None,
+    )
 }
 
 /// Cancel order
@@ -72,7 +91,13 @@ pub fn stop(c: &impl Requests, order_id: u32) -> Result<ActionResult, Box<dyn Er
 /// }
 /// ```
 pub fn cancel(c: &impl Requests, order_id: u32) -> Result<ActionResult, Box<dyn Error>> {
-    self::patch(c, &format!("/orders/{}/action/cancel/", order_id), None)
+    self::patch(
+        c,
+        order_id,
+        Transition::Cancel,
+        &format!("/orders/{}/action/cancel/", order_id),
+        None,
+    )
 }
 
 /// Change online viewers for order
@@ -98,6 +123,8 @@ pub fn change_online(
 ) -> Result<ActionResult, Box<dyn Error>> {
     self::patch(
         c,
+        order_id,
+        Transition::ChangeOnline,
         &format!("/orders/{}/action/change/online/{}/", order_id, value),
         None,
     )
@@ -126,6 +153,8 @@ pub fn change_increase_time(
 ) -> Result<ActionResult, Box<dyn Error>> {
     self::patch(
         c,
+        order_id,
+        Transition::ChangeIncreaseTime,
         &format!("/orders/{}/action/increase/change/{}/", order_id, value),
         None,
     )
@@ -154,6 +183,8 @@ pub fn enable_increase_of_viewers(
 ) -> Result<ActionResult, Box<dyn Error>> {
     self::patch(
         c,
+        order_id,
+        Transition::EnableIncrease,
         &format!("/orders/{}/action/increase/on/{}/", order_id, value),
         None,
     )
@@ -181,6 +212,8 @@ pub fn disable_increase_of_viewers(
 ) -> Result<ActionResult, Box<dyn Error>> {
     self::patch(
         c,
+        order_id,
+        Transition::DisableIncrease,
         &format!("/orders/{}/action/increase/off/", order_id),
         None,
     )
@@ -209,6 +242,8 @@ pub fn add_views(
 ) -> Result<ActionResult, Box<dyn Error>> {
     self::patch(
         c,
+        order_id,
+        Transition::AddViews,
         &format!("/orders/{}/action/add/views/{}/", order_id, value),
         None,
     )
@@ -272,6 +307,8 @@ pub fn change_launch_mode(
     let payload = serde_json::to_string(params)?;
     self::patch(
         c,
+        order_id,
+        Transition::ChangeLaunchMode,
         &format!("/orders/{}/action/change/launch/", order_id),
         Some(payload),
     )
@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::order::{DateAndQuantity, OnlineStats};
+
+/// Parse API timestamps: RFC 3339, `YYYY-MM-DD HH:MM:SS`, `YYYY-MM-DDTHH:MM:SS` or a date.
+/// Values without an offset are treated as UTC.
+///
+/// ```rust
+/// use reydenx::analytics::parse_time;
+///
+/// assert!(parse_time("2024-03-01T12:00:00+03:00").is_some());
+/// assert!(parse_time("2024-03-01 09:00:00").is_some());
+/// assert!(parse_time("2024-03-01").is_some());
+/// assert!(parse_time("yesterday").is_none());
+/// ```
+pub fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Some(t.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(t) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Utc.from_utc_datetime(&t));
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|t| Utc.from_utc_datetime(&t))
+}
+
+/// Resampling interval. Weeks start on Monday, all buckets are in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Bucket {
+    Hour,
+    Day,
+    Week,
+}
+
+impl Bucket {
+    /// Start of the bucket containing the time
+    pub fn floor(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let hour = time
+            .with_minute(0)
+            .and_then(|t| t.with_second(0))
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(time);
+        match self {
+            Bucket::Hour => hour,
+            Bucket::Day => hour.with_hour(0).unwrap_or(hour),
+            Bucket::Week => {
+                let day = hour.with_hour(0).unwrap_or(hour);
+                day - Duration::days(day.weekday().num_days_from_monday() as i64)
+            }
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            Bucket::Hour => Duration::hours(1),
+            Bucket::Day => Duration::days(1),
+            Bucket::Week => Duration::weeks(1),
+        }
+    }
+}
+
+/// How samples within a bucket are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Mean,
+}
+
+/// Value of buckets without samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    /// Leave the bucket out
+    Skip,
+    Zero,
+    /// Repeat the last known value
+    Previous,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub time: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Time series from API quantities, sorted by time. Unparsable dates are skipped.
+pub fn series(data: &[DateAndQuantity]) -> Vec<Point> {
+    let mut points: Vec<Point> = data
+        .iter()
+        .filter_map(|d| {
+            parse_time(&d.date).map(|time| Point {
+                time,
+                value: d.quantity as f64,
+            })
+        })
+        .collect();
+    points.sort_by_key(|p| p.time);
+    points
+}
+
+/// Combine points into buckets between the first and the last point
+///
+/// ```rust
+/// use reydenx::{
+///     analytics::{resample, series, Aggregate, Bucket, Fill},
+///     model::order::DateAndQuantity,
+/// };
+///
+/// let views = series(&[
+///     DateAndQuantity { date: String::from("2024-03-01T10:15:00Z"), quantity: 5 },
+///     DateAndQuantity { date: String::from("2024-03-01T10:45:00Z"), quantity: 7 },
+///     DateAndQuantity { date: String::from("2024-03-01T12:05:00Z"), quantity: 3 },
+/// ]);
+/// let hourly = resample(&views, Bucket::Hour, Aggregate::Sum, Fill::Zero);
+/// let values: Vec<f64> = hourly.iter().map(|p| p.value).collect();
+/// assert_eq!(values, vec![12.0, 0.0, 3.0]);
+/// ```
+pub fn resample(points: &[Point], bucket: Bucket, aggregate: Aggregate, fill: Fill) -> Vec<Point> {
+    let mut buckets: BTreeMap<DateTime<Utc>, (f64, u32)> = BTreeMap::new();
+    for p in points {
+        let entry = buckets.entry(bucket.floor(p.time)).or_insert((0.0, 0));
+        entry.0 += p.value;
+        entry.1 += 1;
+    }
+
+    let (first, last) = match (buckets.keys().next(), buckets.keys().next_back()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return Vec::new(),
+    };
+
+    let mut result = Vec::new();
+    let mut previous = 0.0;
+    let mut time = first;
+    while time <= last {
+        match buckets.get(&time) {
+            Some((sum, count)) => {
+                let value = match aggregate {
+                    Aggregate::Sum => *sum,
+                    Aggregate::Mean => sum / *count as f64,
+                };
+                previous = value;
+                result.push(Point { time, value });
+            }
+            None => match fill {
+                Fill::Skip => {}
+                Fill::Zero => result.push(Point { time, value: 0.0 }),
+                Fill::Previous => result.push(Point {
+                    time,
+                    value: previous,
+                }),
+            },
+        }
+        time = bucket.floor(time + bucket.duration());
+    }
+    result
+}
+
+/// Running total of the series
+pub fn cumulative(points: &[Point]) -> Vec<Point> {
+    let mut total = 0.0;
+    points
+        .iter()
+        .map(|p| {
+            total += p.value;
+            Point {
+                time: p.time,
+                value: total,
+            }
+        })
+        .collect()
+}
+
+/// Click-through rate in percent
+pub fn ctr(views: f64, clicks: f64) -> f64 {
+    if views > 0.0 {
+        clicks / views * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Views and clicks of a bucket together with running totals
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Engagement {
+    pub time: DateTime<Utc>,
+    pub views: f64,
+    pub clicks: f64,
+    pub ctr: f64,
+    pub total_views: f64,
+    pub total_clicks: f64,
+    pub total_ctr: f64,
+}
+
+/// Join `views_stats` and `clicks_stats` by bucket, gaps are filled with zeros
+///
+/// ```rust
+/// use reydenx::{
+///     analytics::{engagement, Bucket},
+///     model::order::DateAndQuantity,
+/// };
+///
+/// let views = vec![
+///     DateAndQuantity { date: String::from("2024-03-01"), quantity: 200 },
+///     DateAndQuantity { date: String::from("2024-03-03"), quantity: 100 },
+/// ];
+/// let clicks = vec![DateAndQuantity { date: String::from("2024-03-01"), quantity: 4 }];
+///
+/// let days = engagement(&views, &clicks, Bucket::Day);
+/// assert_eq!(days.len(), 3);
+/// assert_eq!(days[0].ctr, 2.0);
+/// assert_eq!(days[2].total_views, 300.0);
+/// ```
+pub fn engagement(
+    views: &[DateAndQuantity],
+    clicks: &[DateAndQuantity],
+    bucket: Bucket,
+) -> Vec<Engagement> {
+    let mut joined: BTreeMap<DateTime<Utc>, (f64, f64)> = BTreeMap::new();
+    for p in resample(&series(views), bucket, Aggregate::Sum, Fill::Zero) {
+        joined.entry(p.time).or_default().0 = p.value;
+    }
+    for p in resample(&series(clicks), bucket, Aggregate::Sum, Fill::Zero) {
+        joined.entry(p.time).or_default().1 = p.value;
+    }
+    if let (Some(first), Some(last)) = (
+        joined.keys().next().copied(),
+        joined.keys().next_back().copied(),
+    ) {
+        let mut time = first;
+        while time < last {
+            joined.entry(time).or_default();
+            time = bucket.floor(time + bucket.duration());
+        }
+    }
+
+    let (mut total_views, mut total_clicks) = (0.0, 0.0);
+    joined
+        .into_iter()
+        .map(|(time, (views, clicks))| {
+            total_views += views;
+            total_clicks += clicks;
+            Engagement {
+                time,
+                views,
+                clicks,
+                ctr: ctr(views, clicks),
+                total_views,
+                total_clicks,
+                total_ctr: ctr(total_views, total_clicks),
+            }
+        })
+        .collect()
+}
+
+/// Average ordered and delivered viewers of a bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub time: DateTime<Utc>,
+    pub in_settings: f64,
+    pub in_fact: f64,
+    /// `in_fact / in_settings`, none when nothing was ordered
+    pub ratio: Option<f64>,
+}
+
+/// Delivery ratio of `online_stats` over time
+///
+/// ```rust
+/// use reydenx::{
+///     analytics::{delivery, Bucket, Fill},
+///     model::order::OnlineStats,
+/// };
+///
+/// let online = vec![
+///     OnlineStats {
+///         created_at: String::from("2024-03-01T10:00:00Z"),
+///         in_settings: 100.0,
+///         in_fact: 90.0,
+///     },
+///     OnlineStats {
+///         created_at: String::from("2024-03-01T10:30:00Z"),
+///         in_settings: 100.0,
+///         in_fact: 70.0,
+///     },
+/// ];
+/// let hourly = delivery(&online, Bucket::Hour, Fill::Previous);
+/// assert_eq!(hourly[0].ratio, Some(0.8));
+/// ```
+pub fn delivery(online: &[OnlineStats], bucket: Bucket, fill: Fill) -> Vec<Delivery> {
+    let mut ordered = Vec::new();
+    let mut delivered = Vec::new();
+    for s in online {
+        if let Some(time) = parse_time(&s.created_at) {
+            ordered.push(Point {
+                time,
+                value: s.in_settings,
+            });
+            delivered.push(Point {
+                time,
+                value: s.in_fact,
+            });
+        }
+    }
+    ordered.sort_by_key(|p| p.time);
+    delivered.sort_by_key(|p| p.time);
+
+    resample(&ordered, bucket, Aggregate::Mean, fill)
+        .into_iter()
+        .zip(resample(&delivered, bucket, Aggregate::Mean, fill))
+        .map(|(o, d)| Delivery {
+            time: o.time,
+            in_settings: o.value,
+            in_fact: d.value,
+            ratio: (o.value > 0.0).then(|| d.value / o.value),
+        })
+        .collect()
+}
+
+/// Overall delivery ratio of `online_stats` samples
+pub fn delivery_ratio(online: &[OnlineStats]) -> Option<f64> {
+    let ordered: f64 = online.iter().map(|s| s.in_settings).sum();
+    let delivered: f64 = online.iter().map(|s| s.in_fact).sum();
+    (ordered > 0.0).then(|| delivered / ordered)
+}
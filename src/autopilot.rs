@@ -0,0 +1,342 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action,
+    analytics::parse_time,
+    client::Requests,
+    lifecycle::OrderState,
+    model::{order::OnlineStats, price::MinMaxStep},
+    orders,
+    watch::sleep_until,
+};
+
+/// Target viewers over a stream as straight lines between points
+///
+/// ```rust
+/// use chrono::Duration;
+/// use reydenx::autopilot::Curve;
+///
+/// let curve = Curve::starting_at(50).ramp_to(300, 40).hold(60).taper_to(100, 20);
+/// assert_eq!(curve.target(Duration::minutes(20)), 175);
+/// assert_eq!(curve.target(Duration::minutes(70)), 300);
+/// assert_eq!(curve.target(Duration::minutes(110)), 200);
+/// assert_eq!(curve.duration(), Duration::minutes(120));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Curve {
+    /// Minutes from the start and viewers, ordered by minute
+    pub points: Vec<(u32, u32)>,
+}
+
+impl Curve {
+    pub fn starting_at(viewers: u32) -> Self {
+        Self {
+            points: vec![(0, viewers)],
+        }
+    }
+
+    fn end(&self) -> (u32, u32) {
+        *self.points.last().unwrap_or(&(0, 0))
+    }
+
+    /// Change linearly to `viewers` over `minutes`
+    pub fn ramp_to(mut self, viewers: u32, minutes: u32) -> Self {
+        let (minute, _) = self.end();
+        self.points.push((minute + minutes, viewers));
+        self
+    }
+
+    /// Keep the last value for `minutes`
+    pub fn hold(self, minutes: u32) -> Self {
+        let (_, viewers) = self.end();
+        self.ramp_to(viewers, minutes)
+    }
+
+    /// Same as `ramp_to`, reads better for the way down
+    pub fn taper_to(self, viewers: u32, minutes: u32) -> Self {
+        self.ramp_to(viewers, minutes)
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::minutes(self.end().0 as i64)
+    }
+
+    /// Viewers at `elapsed`, the last value after the curve ends
+    pub fn target(&self, elapsed: Duration) -> u32 {
+        let minute = elapsed.num_seconds().max(0) as f64 / 60.0;
+        for pair in self.points.windows(2) {
+            let (m0, v0) = pair[0];
+            let (m1, v1) = pair[1];
+            if minute <= m1 as f64 {
+                if m1 == m0 {
+                    return v1;
+                }
+                let t = (minute - m0 as f64) / (m1 - m0) as f64;
+                return (v0 as f64 + (v1 as f64 - v0 as f64) * t).round() as u32;
+            }
+        }
+        self.end().1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Decision {
+    Change {
+        from: Option<u32>,
+        to: u32,
+    },
+    /// Setting already matches the curve
+    OnTarget {
+        value: u32,
+    },
+    /// Last change was too recent
+    TooSoon {
+        next_at: DateTime<Utc>,
+    },
+    /// Delivery lags behind the setting, raising it further would not help
+    Lagging {
+        setting: u32,
+        in_fact: f64,
+    },
+    /// Order is completed or cancelled, nothing left to steer
+    Finished {
+        status: String,
+    },
+}
+
+impl Display for Decision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Decision::Change {
+                from: Some(from),
+                to,
+            } => {
+                write!(f, "change online {} -> {}", from, to)
+            }
+            Decision::Change { from: None, to } => write!(f, "change online to {}", to),
+            Decision::OnTarget { value } => write!(f, "on target at {}", value),
+            Decision::TooSoon { next_at } => {
+                write!(f, "waiting until {}", next_at.to_rfc3339())
+            }
+            Decision::Lagging { setting, in_fact } => write!(
+                f,
+                "holding at {}, only {:.0} viewers delivered",
+                setting, in_fact
+            ),
+            Decision::Finished { status } => write!(f, "order is {}", status),
+        }
+    }
+}
+
+/// Drives `change_online` along a viewer curve
+///
+/// ```rust
+/// use chrono::{Duration, TimeZone, Utc};
+/// use reydenx::{
+///     autopilot::{Autopilot, Curve, Decision},
+///     model::price::MinMaxStep,
+/// };
+///
+/// let start = Utc.with_ymd_and_hms(2024, 3, 1, 18, 0, 0).unwrap();
+/// let curve = Curve::starting_at(50).ramp_to(300, 40);
+/// let limits = MinMaxStep { min: 10, max: 1000, step: 10 };
+/// let mut pilot = Autopilot::new(curve, limits).current(50);
+///
+/// let at = start + Duration::minutes(10);
+/// let decision = pilot.decide(at - start, at, Some(49.0));
+/// assert_eq!(decision, Decision::Change { from: Some(50), to: 110 });
+/// pilot.record(110, at);
+///
+/// let at = start + Duration::minutes(12);
+/// assert!(matches!(pilot.decide(at - start, at, Some(105.0)), Decision::TooSoon { .. }));
+///
+/// let at = start + Duration::minutes(20);
+/// let decision = pilot.decide(at - start, at, Some(60.0));
+/// assert_eq!(decision, Decision::Lagging { setting: 110, in_fact: 60.0 });
+/// ```
+#[derive(Debug, Clone)]
+pub struct Autopilot {
+    curve: Curve,
+    limits: MinMaxStep,
+    min_interval: Duration,
+    /// Fraction of the setting that may be missing before raising is paused
+    lag_tolerance: f64,
+    current: Option<u32>,
+    last_change: Option<DateTime<Utc>>,
+}
+
+impl Autopilot {
+    /// Changes at most every 5 minutes, raising pauses when delivery is 25% short
+    pub fn new(curve: Curve, limits: MinMaxStep) -> Self {
+        Self {
+            curve,
+            limits,
+            min_interval: Duration::minutes(5),
+            lag_tolerance: 0.25,
+            current: None,
+            last_change: None,
+        }
+    }
+
+    pub fn min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn lag_tolerance(mut self, lag_tolerance: f64) -> Self {
+        self.lag_tolerance = lag_tolerance;
+        self
+    }
+
+    /// Online limit the order currently has
+    pub fn current(mut self, value: u32) -> Self {
+        self.current = Some(value);
+        self
+    }
+
+    pub fn curve(&self) -> &Curve {
+        &self.curve
+    }
+
+    /// What to do at `elapsed` into the stream given the delivered viewers
+    pub fn decide(&self, elapsed: Duration, now: DateTime<Utc>, in_fact: Option<f64>) -> Decision {
+        let target = self.limits.snap(self.curve.target(elapsed));
+        if self.current == Some(target) {
+            return Decision::OnTarget { value: target };
+        }
+        if let Some(last) = self.last_change {
+            if now - last < self.min_interval {
+                return Decision::TooSoon {
+                    next_at: last + self.min_interval,
+                };
+            }
+        }
+        if let (Some(setting), Some(in_fact)) = (self.current, in_fact) {
+            if target > setting && in_fact < setting as f64 * (1.0 - self.lag_tolerance) {
+                return Decision::Lagging { setting, in_fact };
+            }
+        }
+        Decision::Change {
+            from: self.current,
+            to: target,
+        }
+    }
+
+    /// Remember a change made at `at`
+    pub fn record(&mut self, value: u32, at: DateTime<Utc>) {
+        self.current = Some(value);
+        self.last_change = Some(at);
+    }
+
+    /// Decide for an order started at `started_at` and apply the change
+    pub fn step(
+        &mut self,
+        c: &impl Requests,
+        order_id: u32,
+        started_at: DateTime<Utc>,
+    ) -> Result<Decision, Box<dyn Error>> {
+        let order = orders::order_details(c, order_id)?.result;
+        if OrderState::from_status(&order.status).is_final() {
+            return Ok(Decision::Finished {
+                status: order.status,
+            });
+        }
+        if self.current.is_none() {
+            self.current = Some(order.online_users_limit);
+        }
+        let online = orders::online_stats(c, order_id)?.result;
+        let now = Utc::now();
+        let decision = self.decide(now - started_at, now, latest_in_fact(&online));
+        if let Decision::Change { to, .. } = decision {
+            action::change_online(c, order_id, to)?;
+            self.record(to, now);
+        }
+        Ok(decision)
+    }
+
+    /// Step every `interval` until the curve ends, the order is final or `stop` is set.
+    /// `stop` is also checked while waiting, including the backoff after failed steps.
+    ///
+    /// Failed steps are passed to `on_step` and retried with the interval doubled
+    /// for every failure in a row, up to 16 times the interval.
+    ///
+    /// ```rust,no_run
+    /// use std::sync::atomic::AtomicBool;
+    ///
+    /// use chrono::{Duration, Utc};
+    /// use reydenx::{
+    ///     autopilot::{Autopilot, Curve},
+    ///     client::{Auth, Client},
+    ///     model::price::MinMaxStep,
+    /// };
+    ///
+    /// fn main() {
+    ///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+    ///     if let Ok(client) = client.auth() {
+    ///         let curve = Curve::starting_at(50).ramp_to(300, 40).hold(120).taper_to(50, 20);
+    ///         let limits = MinMaxStep { min: 10, max: 1000, step: 10 };
+    ///         let mut pilot = Autopilot::new(curve, limits);
+    ///         let stop = AtomicBool::new(false);
+    ///         let res = pilot.run(client, 12345, Utc::now(), Duration::minutes(1), &stop, |d| {
+    ///             match d {
+    ///                 Ok(decision) => println!("{}", decision),
+    ///                 Err(e) => eprintln!("{}", e),
+    ///             }
+    ///         });
+    ///         println!("{:?}", res);
+    ///     }
+    /// }
+    /// ```
+    pub fn run(
+        &mut self,
+        c: &impl Requests,
+        order_id: u32,
+        started_at: DateTime<Utc>,
+        interval: Duration,
+        stop: &AtomicBool,
+        on_step: impl Fn(Result<&Decision, &dyn Error>),
+    ) -> Result<(), Box<dyn Error>> {
+        let end = started_at + self.curve.duration();
+        let interval = interval.to_std()?;
+        let mut failures = 0;
+        while !stop.load(Ordering::Relaxed) {
+            match self.step(c, order_id, started_at) {
+                Ok(decision) => {
+                    failures = 0;
+                    on_step(Ok(&decision));
+                    let done = match decision {
+                        Decision::Finished { .. } => true,
+                        Decision::OnTarget { .. } => Utc::now() >= end,
+                        _ => false,
+                    };
+                    if done {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    failures += 1;
+                    on_step(Err(e.as_ref()));
+                }
+            }
+            let delay = interval * 2u32.pow(failures.min(4));
+            sleep_until(Instant::now() + delay, || stop.load(Ordering::Relaxed));
+        }
+        Ok(())
+    }
+}
+
+fn latest_in_fact(online: &[OnlineStats]) -> Option<f64> {
+    online
+        .iter()
+        .filter_map(|s| parse_time(&s.created_at).map(|t| (t, s.in_fact)))
+        .max_by_key(|(t, _)| *t)
+        .map(|(_, in_fact)| in_fact)
+}
@@ -0,0 +1,527 @@
+use std::{
+    env,
+    error::Error,
+    io,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Cell, Gauge, Paragraph, Row, Sparkline, Table, TableState},
+    DefaultTerminal, Frame,
+};
+use reydenx::{
+    action,
+    analytics::parse_time,
+    catalog::PriceCatalog,
+    client::{Auth, Client},
+    config::Config,
+    lifecycle::OrderState,
+    model::{
+        order::{OnlineStats, Order},
+        platform::Platform,
+        price::Price,
+        result::Pages,
+    },
+    orders, pricing,
+};
+
+/// Shortest refresh interval, smaller values are raised to it
+const MIN_REFRESH_SECONDS: u64 = 5;
+
+const HELP: &str = "↑/↓ select  r run  s stop  o change online  v add views  u refresh  q quit";
+
+/// One active order
+struct OrderRow {
+    order: Order,
+    /// Catalog price the order is charged at, if it could be matched
+    price: Option<Price>,
+}
+
+impl OrderRow {
+    fn views(&self) -> u32 {
+        self.order.statistics.as_ref().map_or(0, |s| s.views)
+    }
+
+    fn progress(&self) -> f64 {
+        if self.order.ordered_view_qty == 0 {
+            return 0.0;
+        }
+        (self.views() as f64 / self.order.ordered_view_qty as f64).min(1.0)
+    }
+
+    fn spend(&self) -> f64 {
+        match &self.price {
+            Some(price) => pricing::cost(price, self.views(), 0).0,
+            None => self.views() as f64 * self.order.price_per_view,
+        }
+    }
+}
+
+enum Request {
+    Refresh,
+    /// Order whose online history is shown
+    Focus(u32),
+    Run(u32),
+    Stop(u32),
+    ChangeOnline(u32, u32),
+    AddViews(u32, u32),
+}
+
+enum Update {
+    Orders(Vec<OrderRow>),
+    Online(Online),
+    Message(String),
+}
+
+/// Online history of the selected order, fetched for that order only
+struct Online {
+    order_id: u32,
+    stats: Result<Vec<OnlineStats>, String>,
+}
+
+/// Fetches in the background so the screen keeps responding to keys
+fn worker(
+    mut client: Client,
+    interval: Duration,
+    requests: Receiver<Request>,
+    updates: Sender<Update>,
+) {
+    let mut retry = Duration::from_secs(5);
+    while let Err(e) = client.auth() {
+        let message = format!("sign-in failed: {}, retrying in {}s", e, retry.as_secs());
+        if updates.send(Update::Message(message)).is_err() {
+            return;
+        }
+        match requests.recv_timeout(retry) {
+            Ok(Request::Refresh | Request::Focus(_)) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(_) => {
+                let message = String::from("not signed in yet, try again later");
+                if updates.send(Update::Message(message)).is_err() {
+                    return;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        retry = (retry * 2).min(interval.max(Duration::from_secs(5)));
+    }
+    let c = &client;
+
+    let mut catalog = PriceCatalog::new(Duration::from_secs(3600));
+    let mut focus = None;
+    let mut request = Request::Refresh;
+    loop {
+        let mut refresh = true;
+        let message = match request {
+            Request::Refresh => None,
+            Request::Focus(id) => {
+                focus = Some(id);
+                refresh = false;
+                None
+            }
+            Request::Run(id) => Some((id, "run", action::run(c, id))),
+            Request::Stop(id) => Some((id, "stop", action::stop(c, id))),
+            Request::ChangeOnline(id, value) => {
+                Some((id, "change online", action::change_online(c, id, value)))
+            }
+            Request::AddViews(id, value) => {
+                Some((id, "add views", action::add_views(c, id, value)))
+            }
+        }
+        .map(|(id, name, res)| match res {
+            Ok(res) => format!("{} #{}: task {}", name, id, res.task.id),
+            Err(e) => format!("{} #{} failed: {}", name, id, e),
+        });
+        if let Some(message) = message {
+            if updates.send(Update::Message(message)).is_err() {
+                return;
+            }
+        }
+        if refresh {
+            if let Err(e) = catalog.refresh_if_stale(c) {
+                let message = format!("price refresh failed: {}", e);
+                if updates.send(Update::Message(message)).is_err() {
+                    return;
+                }
+            }
+            let update = match active_orders(c, &catalog) {
+                Ok(rows) => Update::Orders(rows),
+                Err(e) => Update::Message(format!("refresh failed: {}", e)),
+            };
+            if updates.send(update).is_err() {
+                return;
+            }
+        }
+        if let Some(order_id) = focus {
+            let stats = orders::online_stats(c, order_id)
+                .map(|res| res.result)
+                .map_err(|e| e.to_string());
+            if updates
+                .send(Update::Online(Online { order_id, stats }))
+                .is_err()
+            {
+                return;
+            }
+        }
+        request = match requests.recv_timeout(interval) {
+            Ok(request) => request,
+            Err(RecvTimeoutError::Timeout) => Request::Refresh,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+    }
+}
+
+fn active_orders(c: &Client, catalog: &PriceCatalog) -> Result<Vec<OrderRow>, Box<dyn Error>> {
+    let mut rows = Vec::new();
+    for page in Pages::new(|cursor| orders::all_orders(c, cursor)) {
+        for order in page? {
+            if OrderState::from_status(&order.status).is_final() {
+                continue;
+            }
+            let price = order_price(catalog, &order);
+            rows.push(OrderRow { order, price });
+        }
+    }
+    rows.sort_by_key(|r| r.order.id);
+    Ok(rows)
+}
+
+/// Price of the order's platform whose `price`, or price of one view, equals the
+/// order's `price_per_view`. Orders do not carry their price id.
+fn order_price(catalog: &PriceCatalog, order: &Order) -> Option<Price> {
+    let platform: Platform = order.platform.parse().ok()?;
+    catalog
+        .prices(platform)
+        .iter()
+        .find(|p| p.price == order.price_per_view || pricing::unit_price(p) == order.price_per_view)
+        .cloned()
+}
+
+/// Number typed for an order
+#[derive(Clone, Copy)]
+enum Field {
+    Online,
+    Views,
+}
+
+impl Field {
+    fn label(&self) -> &'static str {
+        match self {
+            Field::Online => "online viewers",
+            Field::Views => "views to add",
+        }
+    }
+
+    fn request(&self, order_id: u32, value: u32) -> Request {
+        match self {
+            Field::Online => Request::ChangeOnline(order_id, value),
+            Field::Views => Request::AddViews(order_id, value),
+        }
+    }
+}
+
+/// What the next key presses mean
+enum Mode {
+    Normal,
+    /// Waiting for `y` to run or stop
+    Confirm(Request),
+    Input {
+        order_id: u32,
+        field: Field,
+        value: String,
+    },
+}
+
+struct App {
+    rows: Vec<OrderRow>,
+    online: Option<Online>,
+    /// Order the worker fetches online history for
+    focused: Option<u32>,
+    table: TableState,
+    mode: Mode,
+    message: String,
+    requests: Sender<Request>,
+}
+
+impl App {
+    fn selected(&self) -> Option<&OrderRow> {
+        self.table.selected().and_then(|i| self.rows.get(i))
+    }
+
+    fn set_rows(&mut self, rows: Vec<OrderRow>) {
+        let selected = self.selected().map(|r| r.order.id);
+        self.rows = rows;
+        let index = selected
+            .and_then(|id| self.rows.iter().position(|r| r.order.id == id))
+            .or((!self.rows.is_empty()).then_some(0));
+        self.table.select(index);
+        self.focus();
+    }
+
+    /// Ask for the online history when the selection moved to another order
+    fn focus(&mut self) {
+        let id = self.selected().map(|r| r.order.id);
+        if let Some(id) = id.filter(|id| self.focused != Some(*id)) {
+            self.focused = Some(id);
+            let _ = self.requests.send(Request::Focus(id));
+        }
+    }
+
+    fn send(&mut self, request: Request) {
+        self.message = String::from("working...");
+        let _ = self.requests.send(request);
+    }
+
+    /// False when the dashboard should close
+    fn key(&mut self, code: KeyCode) -> bool {
+        let id = self.selected().map(|r| r.order.id);
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => match (code, id) {
+                (KeyCode::Char('q') | KeyCode::Esc, _) => return false,
+                (KeyCode::Down | KeyCode::Char('j'), _) => {
+                    self.table.select_next();
+                    self.focus();
+                }
+                (KeyCode::Up | KeyCode::Char('k'), _) => {
+                    self.table.select_previous();
+                    self.focus();
+                }
+                (KeyCode::Char('u'), _) => self.send(Request::Refresh),
+                (KeyCode::Char('r'), Some(id)) => {
+                    self.message = format!("run order #{}? y/n", id);
+                    self.mode = Mode::Confirm(Request::Run(id));
+                }
+                (KeyCode::Char('s'), Some(id)) => {
+                    self.message = format!("stop order #{}? y/n", id);
+                    self.mode = Mode::Confirm(Request::Stop(id));
+                }
+                (KeyCode::Char('o'), Some(order_id)) => {
+                    self.mode = Mode::Input {
+                        order_id,
+                        field: Field::Online,
+                        value: String::new(),
+                    }
+                }
+                (KeyCode::Char('v'), Some(order_id)) => {
+                    self.mode = Mode::Input {
+                        order_id,
+                        field: Field::Views,
+                        value: String::new(),
+                    }
+                }
+                _ => {}
+            },
+            Mode::Confirm(request) => match code {
+                KeyCode::Char('y') => self.send(request),
+                _ => self.message = String::from("cancelled"),
+            },
+            Mode::Input {
+                order_id,
+                field,
+                mut value,
+            } => match code {
+                KeyCode::Char(c) if c.is_ascii_digit() => {
+                    value.push(c);
+                    self.mode = Mode::Input {
+                        order_id,
+                        field,
+                        value,
+                    };
+                }
+                KeyCode::Backspace => {
+                    value.pop();
+                    self.mode = Mode::Input {
+                        order_id,
+                        field,
+                        value,
+                    };
+                }
+                KeyCode::Enter => match value.parse() {
+                    Ok(n) => self.send(field.request(order_id, n)),
+                    Err(_) => self.message = String::from("cancelled"),
+                },
+                _ => self.message = String::from("cancelled"),
+            },
+        }
+        true
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [orders, chart, progress, status] = Layout::vertical([
+            Constraint::Min(5),
+            Constraint::Length(8),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let header = Row::new([
+            "id", "platform", "status", "online", "views", "ctr %", "spend",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = self.rows.iter().map(|r| {
+            let statistics = r.order.statistics.as_ref();
+            let online = statistics.map_or(String::from("-"), |s| {
+                format!(
+                    "{:.0} / {:.0}",
+                    s.average.online.in_fact, s.average.online.in_settings
+                )
+            });
+            let ctr = statistics.map_or(String::from("-"), |s| format!("{:.2}", s.ctr));
+            Row::new([
+                Cell::from(r.order.id.to_string()),
+                Cell::from(r.order.platform.clone()),
+                Cell::from(r.order.status.clone()),
+                Cell::from(online),
+                Cell::from(format!(
+                    "{} / {} ({:.0}%)",
+                    r.views(),
+                    r.order.ordered_view_qty,
+                    r.progress() * 100.0
+                )),
+                Cell::from(ctr),
+                Cell::from(format!("{:.2}", r.spend())),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(13),
+                Constraint::Length(24),
+                Constraint::Length(7),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title(" active orders, online is average in fact / in settings "))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, orders, &mut self.table);
+
+        let selected = self.selected();
+        let online = self
+            .online
+            .as_ref()
+            .filter(|o| selected.is_some_and(|r| r.order.id == o.order_id));
+        let data: Vec<u64> = match online.map(|o| &o.stats) {
+            Some(Ok(stats)) => {
+                let mut stats: Vec<&OnlineStats> = stats.iter().collect();
+                stats.sort_by_key(|s| parse_time(&s.created_at));
+                stats.iter().map(|s| s.in_fact.max(0.0) as u64).collect()
+            }
+            _ => Vec::new(),
+        };
+        // newest points on the right
+        let skip = data
+            .len()
+            .saturating_sub(chart.width.saturating_sub(2) as usize);
+        let title = match (selected, online.map(|o| &o.stats)) {
+            (Some(r), Some(Err(e))) => {
+                format!(" online of order #{} unavailable: {} ", r.order.id, e)
+            }
+            (Some(r), _) => format!(" online in fact, order #{} ", r.order.id),
+            (None, _) => String::from(" online "),
+        };
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::bordered().title(title))
+                .data(&data[skip..])
+                .style(Style::default().fg(Color::Green)),
+            chart,
+        );
+
+        let (ratio, label) = selected.map_or((0.0, String::new()), |r| {
+            (
+                r.progress(),
+                format!("{} of {} views", r.views(), r.order.ordered_view_qty),
+            )
+        });
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title(" views "))
+                .gauge_style(Style::default().fg(Color::Cyan))
+                .ratio(ratio)
+                .label(label),
+            progress,
+        );
+
+        let line = match &self.mode {
+            Mode::Input {
+                order_id,
+                field,
+                value,
+            } => format!(
+                "{} for #{}: {}_  enter to send, esc to cancel",
+                field.label(),
+                order_id,
+                value
+            ),
+            _ if self.message.is_empty() => HELP.to_string(),
+            _ => format!("{}  |  {}", self.message, HELP),
+        };
+        frame.render_widget(Paragraph::new(Line::from(line)), status);
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, mut app: App, updates: Receiver<Update>) -> io::Result<()> {
+    loop {
+        while let Ok(update) = updates.try_recv() {
+            match update {
+                Update::Orders(rows) => app.set_rows(rows),
+                Update::Online(online) => app.online = Some(online),
+                Update::Message(message) => app.message = message,
+            }
+        }
+        terminal.draw(|frame| app.draw(frame))?;
+        if event::poll(Duration::from_millis(250))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !app.key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Live view of the active orders.
+///
+/// Usage: reydenx-dashboard [PROFILE] [REFRESH_SECONDS]
+///
+/// Orders refresh every 30 seconds by default and at most every 5.
+///
+/// The profile comes from ~/.config/reydenx/config.toml, see `reydenx::config`.
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let config = Config::load()?;
+    let name = args.next().unwrap_or_else(|| config.default_profile());
+    let client = config.profile(&name)?.client()?;
+    let interval = Duration::from_secs(match args.next() {
+        Some(seconds) => seconds.parse::<u64>()?.max(MIN_REFRESH_SECONDS),
+        None => 30,
+    });
+
+    let (requests, requests_rx) = mpsc::channel();
+    let (updates_tx, updates) = mpsc::channel();
+    thread::spawn(move || worker(client, interval, requests_rx, updates_tx));
+
+    let app = App {
+        rows: Vec::new(),
+        online: None,
+        focused: None,
+        table: TableState::default(),
+        mode: Mode::Normal,
+        message: String::from("loading..."),
+        requests,
+    };
+    let mut terminal = ratatui::init();
+    let res = run(&mut terminal, app, updates);
+    ratatui::restore();
+    Ok(res?)
+}
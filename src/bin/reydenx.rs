@@ -0,0 +1,523 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use chrono::Utc;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use reydenx::{
+    action,
+    client::{Auth, Client},
+    config::Config,
+    export::Record,
+    manifest::{self, Manifest, State},
+    model::{
+        order::{KickPayload, LaunchMode, LaunchParams, SmoothGain, TwitchPayload, YouTubePayload},
+        platform::Platform,
+        result::Pages,
+    },
+    orders, prices,
+    render::{Output, Renderer},
+    traffic, user,
+};
+
+/// Command-line client for the Reyden-X API.
+///
+/// Credentials come from a profile of ~/.config/reydenx/config.toml,
+/// REYDENX_EMAIL and REYDENX_PASSWORD override it. The password is never
+/// taken on the command line, only a file or command to read it from.
+#[derive(Parser)]
+#[command(name = "reydenx", version)]
+struct Cli {
+    /// Profile of the config file
+    #[arg(long, global = true, env = "REYDENX_PROFILE")]
+    profile: Option<String>,
+    #[arg(long, global = true)]
+    email: Option<String>,
+    /// File whose first line is the password
+    #[arg(long, global = true, conflicts_with = "password_command")]
+    password_file: Option<PathBuf>,
+    /// Shell command printing the password
+    #[arg(long, global = true)]
+    password_command: Option<String>,
+    /// table, json, jsonl, csv or template=TEXT with {column} placeholders
+    #[arg(long, short, global = true, default_value = "table")]
+    output: Output,
+    /// Comma-separated columns to print, all by default
+    #[arg(long, global = true, value_delimiter = ',')]
+    columns: Vec<String>,
+    /// Column to sort by
+    #[arg(long, global = true)]
+    sort: Option<String>,
+    /// Sort in descending order
+    #[arg(long, global = true, requires = "sort")]
+    desc: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Orders, their statistics and payments
+    #[command(subcommand)]
+    Orders(OrdersCommand),
+    /// Actions on an order
+    #[command(subcommand)]
+    Action(ActionCommand),
+    /// Status of an action task
+    #[command(subcommand)]
+    Task(TaskCommand),
+    /// Prices of a platform, the profile platform by default
+    Prices { platform: Option<Platform> },
+    /// Price categories
+    Categories,
+    /// Traffic by country, language or device
+    Traffic { kind: TrafficKind },
+    /// Balance and account
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Declarative orders from a TOML or YAML manifest
+    #[command(subcommand)]
+    Manifest(ManifestCommand),
+}
+
+#[derive(Subcommand)]
+enum ManifestCommand {
+    /// Show what apply would create, change or stop
+    Plan {
+        #[command(flatten)]
+        files: ManifestFiles,
+    },
+    /// Execute the plan
+    Apply {
+        #[command(flatten)]
+        files: ManifestFiles,
+        /// Skip the confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Stop tracking the order of an entry, the next apply creates a new one
+    Forget {
+        #[command(flatten)]
+        files: ManifestFiles,
+        name: String,
+    },
+}
+
+#[derive(Args)]
+struct ManifestFiles {
+    manifest: PathBuf,
+    /// Order ids of the manifest entries, MANIFEST.state.json by default
+    #[arg(long)]
+    state: Option<PathBuf>,
+}
+
+impl ManifestFiles {
+    fn state(&self) -> PathBuf {
+        self.state.clone().unwrap_or_else(|| {
+            let mut path = self.manifest.clone().into_os_string();
+            path.push(".state.json");
+            PathBuf::from(path)
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum OrdersCommand {
+    /// First page of orders, every page with --all
+    List {
+        #[arg(long)]
+        all: bool,
+    },
+    Show {
+        order_id: u32,
+    },
+    Stats {
+        kind: StatsKind,
+        order_id: u32,
+    },
+    Payments {
+        order_id: u32,
+    },
+    #[command(subcommand)]
+    Create(CreateCommand),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StatsKind {
+    Online,
+    Views,
+    Clicks,
+    Sites,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TrafficKind {
+    Countries,
+    Languages,
+    Devices,
+}
+
+#[derive(Args)]
+struct StreamArgs {
+    #[arg(long)]
+    price_id: u32,
+    #[arg(long)]
+    views: u32,
+    #[arg(long)]
+    viewers: u32,
+    #[arg(long, default_value = "auto")]
+    launch_mode: String,
+    /// Minutes to delay the start with --launch-mode delay
+    #[arg(long, default_value_t = 0)]
+    delay_time: u32,
+    /// Minutes of smooth gain, disabled when 0
+    #[arg(long, default_value_t = 0)]
+    smooth_gain: u32,
+    #[arg(long, default_value_t = 0)]
+    fixed_allocation: u32,
+    #[arg(long)]
+    on_overflow: bool,
+}
+
+impl StreamArgs {
+    fn smooth_gain(&self) -> SmoothGain {
+        SmoothGain {
+            enabled: self.smooth_gain > 0,
+            minutes: self.smooth_gain,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum CreateCommand {
+    Twitch {
+        #[arg(long)]
+        twitch_id: u32,
+        #[command(flatten)]
+        stream: StreamArgs,
+    },
+    Youtube {
+        #[arg(long)]
+        channel_url: String,
+        #[command(flatten)]
+        stream: StreamArgs,
+    },
+    Kick {
+        #[arg(long)]
+        channel_url: String,
+        #[command(flatten)]
+        stream: StreamArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum ActionCommand {
+    Run {
+        order_id: u32,
+    },
+    Stop {
+        order_id: u32,
+    },
+    Cancel {
+        order_id: u32,
+    },
+    /// Change the online viewers limit
+    Online {
+        order_id: u32,
+        value: u32,
+    },
+    /// Add views
+    Views {
+        order_id: u32,
+        value: u32,
+    },
+    /// Gradual increase of viewers
+    Increase {
+        order_id: u32,
+        #[command(subcommand)]
+        change: IncreaseCommand,
+    },
+    LaunchMode {
+        order_id: u32,
+        /// auto, manual or delay
+        mode: LaunchMode,
+        /// Minutes, from 5 to 240, for the delay mode
+        #[arg(long, default_value_t = 0)]
+        delay_time: u8,
+    },
+}
+
+#[derive(Subcommand)]
+enum IncreaseCommand {
+    Enable {
+        value: u32,
+    },
+    Disable,
+    /// Change the increase time in minutes
+    Time {
+        value: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum TaskCommand {
+    Status { order_id: u32, task_id: String },
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    Balance,
+    Account,
+}
+
+struct Printer {
+    output: Output,
+    columns: Vec<String>,
+    sort: Option<(String, bool)>,
+}
+
+impl Printer {
+    fn print<R: Record>(&self, records: &[R]) -> Result<(), Box<dyn Error>> {
+        let mut renderer = Renderer::<R>::new();
+        if !self.columns.is_empty() {
+            let columns: Vec<&str> = self.columns.iter().map(|c| c.as_str()).collect();
+            renderer = renderer.select(&columns)?;
+        }
+        if let Some((column, descending)) = &self.sort {
+            renderer = renderer.sort_by(column, *descending)?;
+        }
+        renderer.write(io::stdout().lock(), records, &self.output)
+    }
+}
+
+fn orders_command(c: &Client, p: &Printer, command: OrdersCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        OrdersCommand::List { all: false } => p.print(&orders::all_orders(c, None)?.result),
+        OrdersCommand::List { all: true } => {
+            let mut list = Vec::new();
+            for page in Pages::new(|cursor| orders::all_orders(c, cursor)) {
+                list.extend(page?);
+            }
+            p.print(&list)
+        }
+        OrdersCommand::Show { order_id } => p.print(&[orders::order_details(c, order_id)?.result]),
+        OrdersCommand::Stats { kind, order_id } => match kind {
+            StatsKind::Online => p.print(&orders::online_stats(c, order_id)?.result),
+            StatsKind::Views => p.print(&orders::views_stats(c, order_id)?.result),
+            StatsKind::Clicks => p.print(&orders::clicks_stats(c, order_id)?.result),
+            StatsKind::Sites => p.print(&orders::sites_stats(c, order_id)?.result),
+        },
+        OrdersCommand::Payments { order_id } => {
+            let mut list = Vec::new();
+            for page in Pages::new(|cursor| orders::payments(c, order_id, cursor)) {
+                list.extend(page?);
+            }
+            p.print(&list)
+        }
+        OrdersCommand::Create(create) => create_command(c, p, create),
+    }
+}
+
+fn create_command(c: &Client, p: &Printer, command: CreateCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        CreateCommand::Twitch { twitch_id, stream } => p.print(&[orders::create_twitch_stream(
+            c,
+            &TwitchPayload {
+                price_id: stream.price_id,
+                number_of_views: stream.views,
+                number_of_viewers: stream.viewers,
+                launch_mode: stream.launch_mode.clone(),
+                smooth_gain: stream.smooth_gain(),
+                delay_time: stream.delay_time,
+                twitch_id,
+                fixed_allocation: stream.fixed_allocation,
+                on_overflow: stream.on_overflow,
+            },
+        )?]),
+        CreateCommand::Youtube {
+            channel_url,
+            stream,
+        } => p.print(&[orders::create_youtube_stream(
+            c,
+            &YouTubePayload {
+                price_id: stream.price_id,
+                number_of_views: stream.views,
+                number_of_viewers: stream.viewers,
+                launch_mode: stream.launch_mode.clone(),
+                smooth_gain: stream.smooth_gain(),
+                delay_time: stream.delay_time,
+                channel_url,
+                fixed_allocation: stream.fixed_allocation,
+                on_overflow: stream.on_overflow,
+            },
+        )?]),
+        CreateCommand::Kick {
+            channel_url,
+            stream,
+        } => p.print(&[orders::create_kick_stream(
+            c,
+            &KickPayload {
+                price_id: stream.price_id,
+                number_of_views: stream.views,
+                number_of_viewers: stream.viewers,
+                launch_mode: stream.launch_mode.clone(),
+                smooth_gain: stream.smooth_gain(),
+                delay_time: stream.delay_time,
+                channel_url,
+                fixed_allocation: stream.fixed_allocation,
+                on_overflow: stream.on_overflow,
+            },
+        )?]),
+    }
+}
+
+fn action_command(c: &Client, p: &Printer, command: ActionCommand) -> Result<(), Box<dyn Error>> {
+    let res = match command {
+        ActionCommand::Run { order_id } => action::run(c, order_id)?,
+        ActionCommand::Stop { order_id } => action::stop(c, order_id)?,
+        ActionCommand::Cancel { order_id } => action::cancel(c, order_id)?,
+        ActionCommand::Online { order_id, value } => action::change_online(c, order_id, value)?,
+        ActionCommand::Views { order_id, value } => action::add_views(c, order_id, value)?,
+        ActionCommand::Increase { order_id, change } => match change {
+            IncreaseCommand::Enable { value } => {
+                action::enable_increase_of_viewers(c, order_id, value)?
+            }
+            IncreaseCommand::Disable => action::disable_increase_of_viewers(c, order_id)?,
+            IncreaseCommand::Time { value } => action::change_increase_time(c, order_id, value)?,
+        },
+        ActionCommand::LaunchMode {
+            order_id,
+            mode,
+            delay_time,
+        } => action::change_launch_mode(c, order_id, &LaunchParams { mode, delay_time })?,
+    };
+    p.print(&[res])
+}
+
+fn manifest_command(c: &Client, command: ManifestCommand) -> Result<(), Box<dyn Error>> {
+    let (files, yes) = match command {
+        ManifestCommand::Plan { files } => (files, None),
+        ManifestCommand::Apply { files, yes } => (files, Some(yes)),
+        ManifestCommand::Forget { files, name } => {
+            let state_path = files.state();
+            let mut state = State::load(&state_path)?;
+            let order_id = state
+                .forget(&name)
+                .ok_or_else(|| format!("{} is not in {}", name, state_path.display()))?;
+            state.save(&state_path)?;
+            println!("Forgot {} (#{})", name, order_id);
+            return Ok(());
+        }
+    };
+    let manifest = Manifest::load(&files.manifest)?;
+    let state_path = files.state();
+    let mut state = State::load(&state_path)?;
+    let plan = manifest::fetch_plan(c, &manifest, &state, Utc::now())?;
+    print!("{}", plan);
+    let Some(yes) = yes else {
+        return Ok(());
+    };
+    if plan.is_blocked() {
+        return Err("Plan is blocked, see the x lines above".into());
+    }
+    if plan.is_empty() {
+        return Ok(());
+    }
+    if !yes {
+        print!("Apply these steps? Type yes to continue: ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if answer.trim() != "yes" {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+    let res = manifest::apply(c, &plan, &mut state, |step, res| match res {
+        Some(res) => println!("done: {} (task {})", step, res.task.id),
+        None => println!("done: {}", step),
+    });
+    state.save(&state_path)?;
+    res
+}
+
+fn execute(c: &Client, p: &Printer, command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Orders(command) => orders_command(c, p, command),
+        Command::Action(command) => action_command(c, p, command),
+        Command::Task(TaskCommand::Status { order_id, task_id }) => {
+            p.print(&[action::task_status(c, order_id, &task_id)?])
+        }
+        Command::Prices { platform } => {
+            let platform = platform.ok_or("No platform given and none set in the profile")?;
+            p.print(&prices::get_prices(c, platform)?.result)
+        }
+        Command::Categories => p.print(&prices::get_categories(c)?.result),
+        Command::Traffic { kind } => match kind {
+            TrafficKind::Countries => p.print(&traffic::countries(c)?.result),
+            TrafficKind::Languages => p.print(&traffic::languages(c)?.result),
+            TrafficKind::Devices => p.print(&traffic::devices(c)?.result),
+        },
+        Command::User(UserCommand::Balance) => p.print(&[user::balance(c)?]),
+        Command::User(UserCommand::Account) => p.print(&[user::account(c)?]),
+        Command::Manifest(command) => manifest_command(c, command),
+    }
+}
+
+/// Client and default platform of the selected profile
+fn client(cli: &Cli) -> Result<(Client, Option<Platform>), Box<dyn Error>> {
+    let config = Config::load()?;
+    let name = cli
+        .profile
+        .clone()
+        .unwrap_or_else(|| config.default_profile());
+    let mut profile = config.profile(&name)?;
+    if let Some(email) = &cli.email {
+        profile.email = Some(email.clone());
+    }
+    if let Some(path) = &cli.password_file {
+        profile.password = None;
+        profile.password_file = Some(path.clone());
+    }
+    if let Some(command) = &cli.password_command {
+        profile.password = None;
+        profile.password_file = None;
+        profile.credentials_command = Some(command.clone());
+    }
+    Ok((profile.client()?, profile.platform()?))
+}
+
+fn main() -> ExitCode {
+    let mut cli = Cli::parse();
+    let (mut client, default_platform) = match client(&cli) {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Command::Prices { platform: None } = cli.command {
+        cli.command = Command::Prices {
+            platform: default_platform,
+        };
+    }
+    let printer = Printer {
+        output: cli.output,
+        columns: cli.columns,
+        sort: cli.sort.map(|column| (column, cli.desc)),
+    };
+    let res = client
+        .auth()
+        .and_then(|c| execute(c, &printer, cli.command));
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
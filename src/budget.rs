@@ -0,0 +1,378 @@
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    sync::{Mutex, MutexGuard, PoisonError, TryLockError},
+};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    client::Requests,
+    model::{
+        error::{BudgetError, ValueError},
+        order::{KickPayload, OrderPayload, TwitchPayload, YouTubePayload},
+        price::Price,
+        result::ActionResult,
+        user::{Balance, User},
+    },
+    orders, pricing, user,
+};
+
+/// What the guard does when an order breaks a limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardMode {
+    /// Refuse to create the order
+    Block,
+    /// Report the violations and create the order anyway
+    Warn,
+}
+
+/// Spend limits, all amounts are in the balance currency
+#[derive(Debug, Clone)]
+pub struct BudgetLimits {
+    /// Maximum estimated cost of a single order
+    pub per_order: Option<f64>,
+    /// Maximum total spent on orders created through the guard within `period`
+    pub per_period: Option<f64>,
+    pub period: Duration,
+    pub mode: GuardMode,
+}
+
+impl Default for BudgetLimits {
+    fn default() -> Self {
+        Self {
+            per_order: None,
+            per_period: None,
+            period: Duration::days(1),
+            mode: GuardMode::Block,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetViolation {
+    InsufficientBalance { estimate: f64, balance: f64 },
+    PerOrderCap { estimate: f64, cap: f64 },
+    PeriodCap { estimate: f64, spent: f64, cap: f64 },
+}
+
+impl Display for BudgetViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetViolation::InsufficientBalance { estimate, balance } => write!(
+                f,
+                "estimated cost {:.2} exceeds balance {:.2}",
+                estimate, balance
+            ),
+            BudgetViolation::PerOrderCap { estimate, cap } => write!(
+                f,
+                "estimated cost {:.2} exceeds per-order cap {:.2}",
+                estimate, cap
+            ),
+            BudgetViolation::PeriodCap {
+                estimate,
+                spent,
+                cap,
+            } => write!(
+                f,
+                "estimated cost {:.2} with {:.2} already spent exceeds period cap {:.2}",
+                estimate, spent, cap
+            ),
+        }
+    }
+}
+
+/// Result of checking an order against the budget
+#[derive(Debug, Clone)]
+pub struct BudgetCheck {
+    pub estimate: f64,
+    pub balance: f64,
+    pub currency: String,
+    pub spent_in_period: f64,
+    pub violations: Vec<BudgetViolation>,
+}
+
+impl BudgetCheck {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Estimated order cost of `views`, minus the user discount in percent
+///
+/// What a view costs depends on `Price.format`, see `pricing::PriceFormat`.
+/// Same total as `pricing::estimate` without a payload and user at hand.
+///
+/// ```rust
+/// use reydenx::{
+///     budget::estimate_cost,
+///     model::price::{MinMaxStep, Price},
+/// };
+///
+/// let price = Price {
+///     id: 1,
+///     name: String::from("Basic"),
+///     format: String::from("views"),
+///     price: 0.5,
+///     description: String::new(),
+///     views: MinMaxStep { min: 100, max: 100000, step: 100 },
+///     online_viewers: MinMaxStep { min: 10, max: 1000, step: 10 },
+///     category_id: 1,
+/// };
+/// assert_eq!(estimate_cost(&price, 1000, 10), 450.0);
+/// ```
+pub fn estimate_cost(price: &Price, views: u32, discount_value: u32) -> f64 {
+    let (base, discount) = pricing::cost(price, views, discount_value);
+    base - discount
+}
+
+/// Checks orders against the balance and spend caps before creating them
+///
+/// ```rust,no_run
+/// use reydenx::{
+///     budget::{BudgetGuard, BudgetLimits},
+///     client::{Auth, Client},
+///     model::{
+///         order::{SmoothGain, TwitchPayload},
+///         platform::Platform,
+///     },
+///     prices::get_prices,
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         let guard = BudgetGuard::new(BudgetLimits {
+///             per_order: Some(5000.0),
+///             per_period: Some(20000.0),
+///             ..Default::default()
+///         });
+///         let price = get_prices(client, Platform::Twitch).unwrap().result.remove(0);
+///         let res = guard.create_twitch_stream(
+///             client,
+///             &price,
+///             &TwitchPayload {
+///                 price_id: price.id,
+///                 number_of_views: 1000,
+///                 number_of_viewers: 100,
+///                 launch_mode: String::from("auto"),
+///                 smooth_gain: SmoothGain {
+///                     enabled: false,
+///                     minutes: 0,
+///                 },
+///                 delay_time: 0,
+///                 twitch_id: 123456789,
+///                 fixed_allocation: 0,
+///                 on_overflow: false,
+///             },
+///         );
+///         println!("{:#?}", res);
+///     }
+/// }
+/// ```
+pub struct BudgetGuard {
+    limits: BudgetLimits,
+    spent: Mutex<Vec<(DateTime<Utc>, f64)>>,
+    on_warning: Box<dyn Fn(&BudgetViolation) + Send + Sync>,
+}
+
+impl BudgetGuard {
+    pub fn new(limits: BudgetLimits) -> Self {
+        Self {
+            limits,
+            spent: Mutex::new(Vec::new()),
+            on_warning: Box::new(|_| {}),
+        }
+    }
+
+    /// Handler for violations in `GuardMode::Warn`, ignored by default
+    pub fn on_warning(
+        mut self,
+        handler: impl Fn(&BudgetViolation) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_warning = Box::new(handler);
+        self
+    }
+
+    /// Total estimated cost of orders created through the guard within the period
+    pub fn spent_in_period(&self) -> f64 {
+        self.sum_in_period(&self.spent())
+    }
+
+    fn sum_in_period(&self, spent: &[(DateTime<Utc>, f64)]) -> f64 {
+        let since = Utc::now() - self.limits.period;
+        spent
+            .iter()
+            .filter(|(at, _)| *at > since)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+
+    /// Check an order against already fetched user and balance
+    pub fn evaluate(
+        &self,
+        price: &Price,
+        payload: &impl OrderPayload,
+        user: &User,
+        balance: &Balance,
+    ) -> BudgetCheck {
+        self.evaluate_spent(price, payload, user, balance, self.spent_in_period())
+    }
+
+    fn evaluate_spent(
+        &self,
+        price: &Price,
+        payload: &impl OrderPayload,
+        user: &User,
+        balance: &Balance,
+        spent: f64,
+    ) -> BudgetCheck {
+        let estimate = pricing::estimate(price, payload, user).total;
+        let available = balance.amount as f64;
+
+        let mut violations = Vec::new();
+        if estimate > available {
+            violations.push(BudgetViolation::InsufficientBalance {
+                estimate,
+                balance: available,
+            });
+        }
+        if let Some(cap) = self.limits.per_order {
+            if estimate > cap {
+                violations.push(BudgetViolation::PerOrderCap { estimate, cap });
+            }
+        }
+        if let Some(cap) = self.limits.per_period {
+            if spent + estimate > cap {
+                violations.push(BudgetViolation::PeriodCap {
+                    estimate,
+                    spent,
+                    cap,
+                });
+            }
+        }
+
+        BudgetCheck {
+            estimate,
+            balance: available,
+            currency: balance.currency.clone(),
+            spent_in_period: spent,
+            violations,
+        }
+    }
+
+    /// Check an order against the current balance and the limits
+    pub fn check(
+        &self,
+        c: &impl Requests,
+        price: &Price,
+        payload: &impl OrderPayload,
+    ) -> Result<BudgetCheck, Box<dyn Error>> {
+        self.check_spent(c, price, payload, self.spent_in_period())
+    }
+
+    fn check_spent(
+        &self,
+        c: &impl Requests,
+        price: &Price,
+        payload: &impl OrderPayload,
+        spent: f64,
+    ) -> Result<BudgetCheck, Box<dyn Error>> {
+        if price.id != payload.price_id() {
+            return Err(Box::new(ValueError {
+                message: format!(
+                    "Price {} does not match payload price {}",
+                    price.id,
+                    payload.price_id()
+                ),
+            }));
+        }
+        let account = user::account(c)?;
+        let balance = user::balance(c)?;
+        Ok(self.evaluate_spent(price, payload, &account, &balance, spent))
+    }
+
+    /// Create new order for Twitch stream if it fits the budget
+    pub fn create_twitch_stream(
+        &self,
+        c: &impl Requests,
+        price: &Price,
+        payload: &TwitchPayload,
+    ) -> Result<ActionResult, Box<dyn Error>> {
+        self.admit(c, price, payload, || {
+            orders::create_twitch_stream(c, payload)
+        })
+    }
+
+    /// Create new order for YouTube stream if it fits the budget
+    pub fn create_youtube_stream(
+        &self,
+        c: &impl Requests,
+        price: &Price,
+        payload: &YouTubePayload,
+    ) -> Result<ActionResult, Box<dyn Error>> {
+        self.admit(c, price, payload, || {
+            orders::create_youtube_stream(c, payload)
+        })
+    }
+
+    /// Create new order for Kick stream if it fits the budget
+    pub fn create_kick_stream(
+        &self,
+        c: &impl Requests,
+        price: &Price,
+        payload: &KickPayload,
+    ) -> Result<ActionResult, Box<dyn Error>> {
+        self.admit(c, price, payload, || orders::create_kick_stream(c, payload))
+    }
+
+    /// Check and record under one lock, so concurrent creates can't both pass the same budget
+    fn admit(
+        &self,
+        c: &impl Requests,
+        price: &Price,
+        payload: &impl OrderPayload,
+        create: impl FnOnce() -> Result<ActionResult, Box<dyn Error>>,
+    ) -> Result<ActionResult, Box<dyn Error>> {
+        let (res, warnings) = {
+            let mut spent = self.spent();
+            let since = Utc::now() - self.limits.period;
+            spent.retain(|(at, _)| *at > since);
+            let check = self.check_spent(c, price, payload, self.sum_in_period(&spent))?;
+            if !check.is_ok() && self.limits.mode == GuardMode::Block {
+                return Err(Box::new(BudgetError {
+                    estimate: check.estimate,
+                    reasons: check.violations.iter().map(|v| v.to_string()).collect(),
+                }));
+            }
+            let res = create();
+            if res.is_ok() {
+                spent.push((Utc::now(), check.estimate));
+            }
+            (res, check.violations)
+        };
+        // handlers run unlocked so they may use the guard
+        warnings.iter().for_each(|v| (self.on_warning)(v));
+        res
+    }
+
+    /// Spends of the guard, still usable after a panic while they were locked
+    fn spent(&self) -> MutexGuard<'_, Vec<(DateTime<Utc>, f64)>> {
+        self.spent.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Debug for BudgetGuard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // none while a create holds the lock, so handlers can print the guard
+        let spent = match self.spent.try_lock() {
+            Ok(spent) => Some(self.sum_in_period(&spent)),
+            Err(TryLockError::Poisoned(e)) => Some(self.sum_in_period(&e.into_inner())),
+            Err(TryLockError::WouldBlock) => None,
+        };
+        f.debug_struct("BudgetGuard")
+            .field("limits", &self.limits)
+            .field("spent_in_period", &spent)
+            .finish()
+    }
+}
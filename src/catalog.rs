@@ -0,0 +1,234 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    client::Requests,
+    model::{platform::Platform, price::Price, price_category::PriceCategory},
+    prices::{get_categories, get_prices},
+    pricing::unit_price,
+};
+
+/// Requirements for a price lookup
+#[derive(Debug, Clone)]
+pub struct PriceQuery {
+    pub platform: Platform,
+    pub category_id: Option<u32>,
+    pub viewers: Option<u32>,
+    pub views: Option<u32>,
+}
+
+impl PriceQuery {
+    pub fn new(platform: Platform) -> Self {
+        Self {
+            platform,
+            category_id: None,
+            viewers: None,
+            views: None,
+        }
+    }
+}
+
+/// Prices and categories of all platforms, loaded once and refreshed after `ttl`
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use reydenx::{
+///     catalog::{PriceCatalog, PriceQuery},
+///     client::{Auth, Client},
+///     model::platform::Platform,
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         let mut catalog = PriceCatalog::new(Duration::from_secs(3600));
+///         if catalog.refresh_if_stale(client).is_ok() {
+///             let res = catalog.cheapest(&PriceQuery {
+///                 viewers: Some(300),
+///                 views: Some(5000),
+///                 ..PriceQuery::new(Platform::Twitch)
+///             });
+///             println!("{:#?}", res);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PriceCatalog {
+    ttl: Duration,
+    platforms: Vec<Platform>,
+    loaded_at: Option<Instant>,
+    prices: HashMap<Platform, Vec<Price>>,
+    categories: HashMap<u32, PriceCategory>,
+}
+
+impl PriceCatalog {
+    /// Empty catalog for all platforms, filled on the first refresh
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_platforms(ttl, Platform::ALL.to_vec())
+    }
+
+    /// Empty catalog limited to the given platforms
+    pub fn with_platforms(ttl: Duration, platforms: Vec<Platform>) -> Self {
+        Self {
+            ttl,
+            platforms,
+            loaded_at: None,
+            prices: HashMap::new(),
+            categories: HashMap::new(),
+        }
+    }
+
+    /// Catalog built from already fetched data
+    pub fn from_parts(
+        ttl: Duration,
+        prices: HashMap<Platform, Vec<Price>>,
+        categories: Vec<PriceCategory>,
+    ) -> Self {
+        Self {
+            ttl,
+            platforms: prices.keys().copied().collect(),
+            loaded_at: Some(Instant::now()),
+            prices,
+            categories: categories.into_iter().map(|c| (c.id, c)).collect(),
+        }
+    }
+
+    pub fn is_stale(&self) -> bool {
+        match self.loaded_at {
+            Some(at) => at.elapsed() >= self.ttl,
+            None => true,
+        }
+    }
+
+    /// Fetch prices of every platform and all categories
+    pub fn refresh(&mut self, c: &impl Requests) -> Result<(), Box<dyn Error>> {
+        let mut prices = HashMap::new();
+        for platform in &self.platforms {
+            prices.insert(*platform, get_prices(c, *platform)?.result);
+        }
+        let categories = get_categories(c)?.result;
+
+        self.prices = prices;
+        self.categories = categories.into_iter().map(|c| (c.id, c)).collect();
+        self.loaded_at = Some(Instant::now());
+        Ok(())
+    }
+
+    pub fn refresh_if_stale(&mut self, c: &impl Requests) -> Result<(), Box<dyn Error>> {
+        if self.is_stale() {
+            self.refresh(c)?;
+        }
+        Ok(())
+    }
+
+    pub fn prices(&self, platform: Platform) -> &[Price] {
+        self.prices.get(&platform).map_or(&[], |p| p.as_slice())
+    }
+
+    pub fn categories(&self) -> impl Iterator<Item = &PriceCategory> {
+        self.categories.values()
+    }
+
+    pub fn category(&self, category_id: u32) -> Option<&PriceCategory> {
+        self.categories.get(&category_id)
+    }
+
+    pub fn by_category(&self, platform: Platform, category_id: u32) -> Vec<&Price> {
+        self.prices(platform)
+            .iter()
+            .filter(|p| p.category_id == category_id)
+            .collect()
+    }
+
+    /// Price by id together with its platform
+    pub fn price(&self, price_id: u32) -> Option<(Platform, &Price)> {
+        self.prices.iter().find_map(|(platform, prices)| {
+            prices
+                .iter()
+                .find(|p| p.id == price_id)
+                .map(|p| (*platform, p))
+        })
+    }
+
+    /// Prices in an unknown category are treated as active
+    pub fn is_active(&self, price: &Price) -> bool {
+        self.category(price.category_id).is_none_or(|c| c.is_active)
+    }
+
+    /// Active prices matching the query, cheapest per view first
+    ///
+    /// ```rust
+    /// use std::{collections::HashMap, time::Duration};
+    ///
+    /// use reydenx::{
+    ///     catalog::{PriceCatalog, PriceQuery},
+    ///     model::{
+    ///         platform::Platform,
+    ///         price::{MinMaxStep, Price},
+    ///         price_category::PriceCategory,
+    ///     },
+    /// };
+    ///
+    /// let price = |id, format: &str, price, category_id, max_viewers| Price {
+    ///     id,
+    ///     name: format!("Price {}", id),
+    ///     format: format.to_string(),
+    ///     price,
+    ///     description: String::new(),
+    ///     views: MinMaxStep { min: 100, max: 100000, step: 100 },
+    ///     online_viewers: MinMaxStep { min: 10, max: max_viewers, step: 10 },
+    ///     category_id,
+    /// };
+    /// let category = |id, is_active| PriceCategory {
+    ///     id,
+    ///     is_active,
+    ///     name: String::new(),
+    ///     description: String::new(),
+    /// };
+    /// let catalog = PriceCatalog::from_parts(
+    ///     Duration::from_secs(3600),
+    ///     HashMap::from([(
+    ///         Platform::Twitch,
+    ///         vec![
+    ///             price(1, "views", 0.5, 1, 1000),
+    ///             price(2, "per_1000", 300.0, 1, 1000),
+    ///             price(3, "views", 0.1, 2, 1000),
+    ///             price(4, "views", 0.4, 1, 100),
+    ///         ],
+    ///     )]),
+    ///     vec![category(1, true), category(2, false)],
+    /// );
+    ///
+    /// let query = PriceQuery::new(Platform::Twitch);
+    /// let ids: Vec<u32> = catalog.find(&query).iter().map(|p| p.id).collect();
+    /// assert_eq!(ids, vec![2, 4, 1]);
+    ///
+    /// let query = PriceQuery { viewers: Some(300), ..query };
+    /// assert_eq!(catalog.cheapest(&query).map(|p| p.id), Some(2));
+    ///
+    /// let query = PriceQuery { views: Some(150), ..query };
+    /// assert!(catalog.cheapest(&query).is_none());
+    /// ```
+    pub fn find(&self, query: &PriceQuery) -> Vec<&Price> {
+        let mut found: Vec<&Price> = self
+            .prices(query.platform)
+            .iter()
+            .filter(|p| self.is_active(p))
+            .filter(|p| query.category_id.is_none_or(|id| p.category_id == id))
+            .filter(|p| query.viewers.is_none_or(|v| p.online_viewers.allows(v)))
+            .filter(|p| query.views.is_none_or(|v| p.views.allows(v)))
+            .collect();
+        found.sort_by(|a, b| unit_price(a).total_cmp(&unit_price(b)));
+        found
+    }
+
+    /// Cheapest active price matching the query
+    pub fn cheapest(&self, query: &PriceQuery) -> Option<&Price> {
+        self.find(query).into_iter().next()
+    }
+}
@@ -1,10 +1,22 @@
-use std::{error::Error, time::Duration};
+use std::{
+    error::Error,
+    fmt::{self, Debug, Formatter},
+    time::Duration,
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::model::{error::ResponseError, token::Token};
+use crate::{
+    config::Config,
+    credentials::{CredentialProvider, Secret, StaticCredentials},
+    lifecycle::OrderCache,
+    model::{error::ResponseError, token::Token},
+};
 
-const BASE_URL: &'static str = "https://api.reyden-x.com/v1";
+pub const BASE_URL: &str = "https://api.reyden-x.com/v1";
+
+/// Default timeout of a request
+pub const TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Detail {
@@ -31,6 +43,12 @@ pub trait Requests {
     fn delete(&self, path: &str) -> Result<String, Box<dyn Error>>;
 
     fn patch(&self, path: &str, payload: Option<String>) -> Result<String, Box<dyn Error>>;
+
+    /// Orders used to check actions against the order lifecycle.
+    /// Checks are disabled when `None`.
+    fn order_cache(&self) -> Option<&OrderCache> {
+        None
+    }
 }
 
 pub trait Auth<T> {
@@ -41,26 +59,95 @@ pub trait Auth<T> {
     fn get_token(&self) -> &Token;
 }
 
-#[derive(Debug)]
 pub struct Client {
     client: reqwest::blocking::Client,
-    username: String,
-    password: String,
+    credentials: Box<dyn CredentialProvider>,
+    base_url: String,
+    timeout: Duration,
     token: Token,
+    order_cache: Option<OrderCache>,
 }
 
 impl Client {
     pub fn new(username: String, password: String) -> Self {
+        Self::with_credentials(StaticCredentials::new(username, Secret::new(password)))
+    }
+
+    /// Client asking `provider` for the login whenever it authenticates
+    ///
+    /// ```rust,no_run
+    /// use reydenx::{
+    ///     client::{Auth, Client},
+    ///     credentials::CommandCredentials,
+    /// };
+    ///
+    /// fn main() {
+    ///     let provider = CommandCredentials::new(String::from("EMAIL"), "pass show reyden-x");
+    ///     let mut client = Client::with_credentials(provider);
+    ///     if let Ok(client) = client.auth() {
+    ///         println!("{:?}", client);
+    ///     }
+    /// }
+    /// ```
+    pub fn with_credentials(provider: impl CredentialProvider + 'static) -> Self {
         Self {
             client: reqwest::blocking::Client::new(),
-            username,
-            password,
+            credentials: Box::new(provider),
+            base_url: BASE_URL.to_string(),
+            timeout: TIMEOUT,
             token: Token {
-                access_token: "".to_string(),
+                access_token: Secret::default(),
                 expires_in: "".to_string(),
             },
+            order_cache: None,
         }
     }
+
+    /// Client for a profile of the config file, see `config::Config`.
+    /// Environment variables override the file.
+    ///
+    /// ```rust,no_run
+    /// use reydenx::client::{Auth, Client};
+    ///
+    /// fn main() {
+    ///     let mut client = Client::from_profile("prod").unwrap();
+    ///     if let Ok(client) = client.auth() {
+    ///         println!("{:?}", reydenx::user::balance(client));
+    ///     }
+    /// }
+    /// ```
+    pub fn from_profile(name: &str) -> Result<Self, Box<dyn Error>> {
+        Config::load()?.profile(name)?.client()
+    }
+
+    /// API root, `BASE_URL` by default
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Check order actions against the order lifecycle before sending them.
+    /// Orders are fetched on demand and cached for `ttl`.
+    pub fn with_transition_checks(mut self, ttl: Duration) -> Self {
+        self.order_cache = Some(OrderCache::new(ttl));
+        self
+    }
+}
+
+impl Debug for Client {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("token", &self.token)
+            .field("order_cache", &self.order_cache)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Auth<Client> for Client {
@@ -69,17 +156,22 @@ impl Auth<Client> for Client {
             return Ok(self);
         }
 
-        let params = [("username", &self.username), ("password", &self.password)];
+        let credentials = self.credentials.credentials()?;
+        let params = [
+            ("username", credentials.username.as_str()),
+            ("password", credentials.password.expose()),
+        ];
         let resp = self
             .client
-            .post(BASE_URL.to_owned() + "/token/")
+            .post(self.base_url.clone() + "/token/")
             .form(&params)
+            .timeout(self.timeout)
             .send()?;
 
         match resp.status() {
             reqwest::StatusCode::OK => {
                 let text = &resp.text().unwrap();
-                let token: Token = serde_json::from_str(&text)?;
+                let token: Token = serde_json::from_str(text)?;
                 self.token = token;
                 Ok(self)
             }
@@ -114,7 +206,7 @@ impl Requests for Client {
         path: &str,
         payload: Option<String>,
     ) -> Result<String, Box<dyn Error>> {
-        let full_path = String::from(self::BASE_URL.to_string() + path);
+        let full_path = self.base_url.clone() + path;
         let cl = match method {
             reqwest::Method::POST => match payload {
                 Some(data) => self
@@ -132,10 +224,10 @@ impl Requests for Client {
         let resp = cl
             .header(
                 "Authorization",
-                format!("Bearer {}", self.get_token().access_token),
+                format!("Bearer {}", self.get_token().access_token.expose()),
             )
             .header("Accept", "application/json")
-            .timeout(Duration::new(5, 0))
+            .timeout(self.timeout)
             .send()?;
 
         match resp.status() {
@@ -178,4 +270,8 @@ impl Requests for Client {
     fn patch(&self, path: &str, payload: Option<String>) -> Result<String, Box<dyn Error>> {
         self.request(reqwest::Method::PATCH, path, payload)
     }
+
+    fn order_cache(&self) -> Option<&OrderCache> {
+        self.order_cache.as_ref()
+    }
 }
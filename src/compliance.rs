@@ -0,0 +1,261 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analytics::parse_time,
+    client::Requests,
+    model::order::{OnlineStats, Order},
+    orders,
+};
+
+/// A continuous period when delivered viewers stayed below tolerance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shortfall {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub minutes: f64,
+    /// Ordered minus delivered viewer-minutes within the window
+    pub missing_viewer_minutes: f64,
+    pub lowest_ratio: f64,
+}
+
+/// Delivery of an order compared with what was ordered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceReport {
+    pub order_id: u32,
+    /// Allowed shortfall as a fraction of ordered viewers, e.g. 0.1 for 10%
+    pub tolerance: f64,
+    pub samples: usize,
+    pub observed_minutes: f64,
+    /// Percent of observed time when delivery stayed within tolerance
+    pub within_tolerance_percent: f64,
+    pub ordered_viewer_minutes: f64,
+    pub delivered_viewer_minutes: f64,
+    pub average_in_settings: Option<f64>,
+    pub average_in_fact: Option<f64>,
+    pub ordered_views: u32,
+    pub delivered_views: Option<u32>,
+    /// Longest first
+    pub shortfalls: Vec<Shortfall>,
+}
+
+impl ComplianceReport {
+    pub fn delivered_viewer_minutes_percent(&self) -> Option<f64> {
+        (self.ordered_viewer_minutes > 0.0)
+            .then(|| self.delivered_viewer_minutes / self.ordered_viewer_minutes * 100.0)
+    }
+
+    pub fn delivered_views_percent(&self) -> Option<f64> {
+        match self.delivered_views {
+            Some(views) if self.ordered_views > 0 => {
+                Some(views as f64 / self.ordered_views as f64 * 100.0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Report as Markdown, e.g. for a support ticket
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        md.push_str(&format!(
+            "# Delivery report for order {}\n\n",
+            self.order_id
+        ));
+        md.push_str("| Metric | Value |\n|---|---|\n");
+        md.push_str(&format!(
+            "| Observed time | {:.0} min ({} samples) |\n",
+            self.observed_minutes, self.samples
+        ));
+        md.push_str(&format!(
+            "| Within {:.0}% tolerance | {:.1}% of time |\n",
+            self.tolerance * 100.0,
+            self.within_tolerance_percent
+        ));
+        md.push_str(&format!(
+            "| Viewer-minutes delivered / ordered | {:.0} / {:.0}{} |\n",
+            self.delivered_viewer_minutes,
+            self.ordered_viewer_minutes,
+            percent(self.delivered_viewer_minutes_percent())
+        ));
+        if let (Some(ordered), Some(delivered)) = (self.average_in_settings, self.average_in_fact) {
+            md.push_str(&format!(
+                "| Average online delivered / ordered | {:.1} / {:.1} |\n",
+                delivered, ordered
+            ));
+        }
+        md.push_str(&format!(
+            "| Views delivered / ordered | {} / {}{} |\n",
+            self.delivered_views
+                .map_or(String::from("n/a"), |v| v.to_string()),
+            self.ordered_views,
+            percent(self.delivered_views_percent())
+        ));
+
+        md.push_str("\n## Longest shortfalls\n\n");
+        if self.shortfalls.is_empty() {
+            md.push_str("No shortfalls below tolerance.\n");
+            return md;
+        }
+        md.push_str("| Start | End | Minutes | Missing viewer-minutes | Lowest delivery |\n");
+        md.push_str("|---|---|---|---|---|\n");
+        for s in &self.shortfalls {
+            md.push_str(&format!(
+                "| {} | {} | {:.0} | {:.0} | {:.1}% |\n",
+                s.start.format("%Y-%m-%d %H:%M"),
+                s.end.format("%Y-%m-%d %H:%M"),
+                s.minutes,
+                s.missing_viewer_minutes,
+                s.lowest_ratio * 100.0
+            ));
+        }
+        md
+    }
+}
+
+fn percent(value: Option<f64>) -> String {
+    value.map_or(String::new(), |v| format!(" ({:.1}%)", v))
+}
+
+impl Display for ComplianceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_markdown())
+    }
+}
+
+/// Build a compliance report from an order and its online statistics.
+///
+/// Every sample is assumed to hold until the next one, the last sample
+/// carries no duration.
+///
+/// ```rust
+/// use reydenx::{compliance::report, model::order::OnlineStats};
+///
+/// let sample = |minute: u32, in_fact: f64| OnlineStats {
+///     created_at: format!("2024-03-01T10:{:02}:00Z", minute),
+///     in_settings: 100.0,
+///     in_fact,
+/// };
+/// let online = vec![sample(0, 98.0), sample(10, 60.0), sample(20, 95.0), sample(30, 97.0)];
+///
+/// let res = report(12345, &online, None, 0.1);
+/// assert_eq!(res.observed_minutes, 30.0);
+/// assert_eq!(res.shortfalls.len(), 1);
+/// assert_eq!(res.shortfalls[0].minutes, 10.0);
+/// assert!((res.within_tolerance_percent - 66.67).abs() < 0.01);
+/// ```
+pub fn report(
+    order_id: u32,
+    online: &[OnlineStats],
+    order: Option<&Order>,
+    tolerance: f64,
+) -> ComplianceReport {
+    let mut samples: Vec<(DateTime<Utc>, &OnlineStats)> = online
+        .iter()
+        .filter_map(|s| parse_time(&s.created_at).map(|t| (t, s)))
+        .collect();
+    samples.sort_by_key(|(t, _)| *t);
+
+    let mut observed = 0.0;
+    let mut within = 0.0;
+    let mut ordered_vm = 0.0;
+    let mut delivered_vm = 0.0;
+    let mut shortfalls: Vec<Shortfall> = Vec::new();
+    let mut current: Option<Shortfall> = None;
+
+    for pair in samples.windows(2) {
+        let (start, s) = pair[0];
+        let end = pair[1].0;
+        let minutes = (end - start).num_seconds() as f64 / 60.0;
+        observed += minutes;
+        ordered_vm += s.in_settings * minutes;
+        delivered_vm += s.in_fact * minutes;
+
+        let ratio = if s.in_settings > 0.0 {
+            s.in_fact / s.in_settings
+        } else {
+            1.0
+        };
+        if ratio >= 1.0 - tolerance {
+            within += minutes;
+            if let Some(done) = current.take() {
+                shortfalls.push(done);
+            }
+            continue;
+        }
+
+        let missing = (s.in_settings - s.in_fact) * minutes;
+        match current.as_mut() {
+            Some(w) => {
+                w.end = end;
+                w.minutes += minutes;
+                w.missing_viewer_minutes += missing;
+                w.lowest_ratio = w.lowest_ratio.min(ratio);
+            }
+            None => {
+                current = Some(Shortfall {
+                    start,
+                    end,
+                    minutes,
+                    missing_viewer_minutes: missing,
+                    lowest_ratio: ratio,
+                })
+            }
+        }
+    }
+    if let Some(done) = current {
+        shortfalls.push(done);
+    }
+    shortfalls.sort_by(|a, b| b.minutes.total_cmp(&a.minutes));
+
+    let statistics = order.and_then(|o| o.statistics.as_ref());
+    ComplianceReport {
+        order_id,
+        tolerance,
+        samples: samples.len(),
+        observed_minutes: observed,
+        within_tolerance_percent: if observed > 0.0 {
+            within / observed * 100.0
+        } else {
+            100.0
+        },
+        ordered_viewer_minutes: ordered_vm,
+        delivered_viewer_minutes: delivered_vm,
+        average_in_settings: statistics.map(|s| s.average.online.in_settings),
+        average_in_fact: statistics.map(|s| s.average.online.in_fact),
+        ordered_views: order.map_or(0, |o| o.ordered_view_qty),
+        delivered_views: statistics.map(|s| s.views),
+        shortfalls,
+    }
+}
+
+/// Fetch the order with its online statistics and build a compliance report
+///
+/// ```rust,no_run
+/// use reydenx::{
+///     client::{Auth, Client},
+///     compliance::fetch_report,
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         if let Ok(res) = fetch_report(client, 12345, 0.1) {
+///             println!("{}", res.to_markdown());
+///         }
+///     }
+/// }
+/// ```
+pub fn fetch_report(
+    c: &impl Requests,
+    order_id: u32,
+    tolerance: f64,
+) -> Result<ComplianceReport, Box<dyn Error>> {
+    let order = orders::order_details(c, order_id)?.result;
+    let online = orders::online_stats(c, order_id)?.result;
+    Ok(report(order_id, &online, Some(&order), tolerance))
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::Client,
+    credentials::{
+        CommandCredentials, CredentialProvider, FileCredentials, Secret, StaticCredentials,
+    },
+    model::{error::ValueError, platform::Platform},
+};
+
+/// Profile used when none is named
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Connection settings of one account
+///
+/// Every field can be overridden from the environment:
+/// `REYDENX_EMAIL`, `REYDENX_PASSWORD`, `REYDENX_PASSWORD_FILE`, `REYDENX_CREDENTIALS_COMMAND`,
+/// `REYDENX_BASE_URL`, `REYDENX_PLATFORM` and `REYDENX_TIMEOUT`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub email: Option<String>,
+    pub password: Option<Secret>,
+    /// File whose first line is the password
+    pub password_file: Option<PathBuf>,
+    /// Shell command printing the password, used when `password` is not set
+    pub credentials_command: Option<String>,
+    pub base_url: Option<String>,
+    /// Platform used when a tool is not given one
+    pub platform: Option<String>,
+    /// Request timeout in seconds
+    pub timeout: Option<u64>,
+}
+
+impl Profile {
+    /// Replace fields with the values `lookup` returns for their variables
+    ///
+    /// ```rust
+    /// use reydenx::config::Profile;
+    ///
+    /// let profile = Profile {
+    ///     email: Some(String::from("me@example.com")),
+    ///     timeout: Some(10),
+    ///     ..Default::default()
+    /// };
+    /// let profile = profile.with_env(|name| match name {
+    ///     "REYDENX_TIMEOUT" => Some(String::from("30")),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(profile.email.as_deref(), Some("me@example.com"));
+    /// assert_eq!(profile.timeout, Some(30));
+    /// ```
+    pub fn with_env(mut self, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let vars: [(&str, &mut Option<String>); 4] = [
+            ("REYDENX_EMAIL", &mut self.email),
+            ("REYDENX_CREDENTIALS_COMMAND", &mut self.credentials_command),
+            ("REYDENX_BASE_URL", &mut self.base_url),
+            ("REYDENX_PLATFORM", &mut self.platform),
+        ];
+        for (name, field) in vars {
+            if let Some(value) = lookup(name).filter(|v| !v.is_empty()) {
+                *field = Some(value);
+            }
+        }
+        if let Some(password) = lookup("REYDENX_PASSWORD").filter(|v| !v.is_empty()) {
+            self.password = Some(Secret::new(password));
+        }
+        if let Some(path) = lookup("REYDENX_PASSWORD_FILE").filter(|v| !v.is_empty()) {
+            self.password_file = Some(PathBuf::from(path));
+        }
+        if let Some(timeout) = lookup("REYDENX_TIMEOUT").and_then(|v| v.parse().ok()) {
+            self.timeout = Some(timeout);
+        }
+        self
+    }
+
+    pub fn platform(&self) -> Result<Option<Platform>, ValueError> {
+        self.platform.as_deref().map(str::parse).transpose()
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs)
+    }
+
+    /// Provider for the login: `password`, else `password_file`, else `credentials_command`
+    pub fn credential_provider(&self) -> Result<Box<dyn CredentialProvider>, ValueError> {
+        let email = self.email.clone().ok_or_else(|| ValueError {
+            message: String::from("Profile has no email"),
+        })?;
+        if let Some(password) = &self.password {
+            Ok(Box::new(StaticCredentials::new(email, password.clone())))
+        } else if let Some(path) = &self.password_file {
+            Ok(Box::new(FileCredentials::new(email, path)))
+        } else if let Some(command) = &self.credentials_command {
+            Ok(Box::new(CommandCredentials::new(email, command)))
+        } else {
+            Err(ValueError {
+                message: String::from(
+                    "Profile has no password, password file or credentials command",
+                ),
+            })
+        }
+    }
+
+    pub fn client(&self) -> Result<Client, Box<dyn Error>> {
+        let mut client = Client::with_credentials(self.credential_provider()?);
+        if let Some(base_url) = &self.base_url {
+            client = client.base_url(base_url);
+        }
+        if let Some(timeout) = self.timeout() {
+            client = client.timeout(timeout);
+        }
+        Ok(client)
+    }
+}
+
+/// Named profiles of `~/.config/reydenx/config.toml`
+///
+/// ```rust
+/// use reydenx::{config::Config, credentials::CredentialProvider, model::platform::Platform};
+///
+/// let config = Config::from_toml(r#"
+///     default_profile = "prod"
+///
+///     [profile.prod]
+///     email = "me@example.com"
+///     credentials_command = "pass show reyden-x"
+///     platform = "twitch"
+///
+///     [profile.staging]
+///     email = "test@example.com"
+///     password = "secret"
+///     base_url = "https://staging.example.com/v1"
+///     timeout = 20
+/// "#).unwrap();
+///
+/// let prod = config.get("prod").unwrap();
+/// assert_eq!(prod.platform().unwrap(), Some(Platform::Twitch));
+/// assert_eq!(config.default_profile(), "prod");
+///
+/// let staging = config.get("staging").unwrap();
+/// let login = staging.credential_provider().unwrap().credentials().unwrap();
+/// assert_eq!(login.password.expose(), "secret");
+/// assert!(config.get("dev").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    pub default_profile: Option<String>,
+    #[serde(default, rename = "profile")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    pub fn from_toml(text: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// `REYDENX_CONFIG`, else `reydenx/config.toml` in `XDG_CONFIG_HOME` or `~/.config`
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("REYDENX_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(dir.join("reydenx").join("config.toml"))
+    }
+
+    /// Config at `Config::path`, empty if there is no file
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::load_from(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// `REYDENX_PROFILE`, else `default_profile`, else `DEFAULT_PROFILE`
+    pub fn default_profile(&self) -> String {
+        env::var("REYDENX_PROFILE")
+            .ok()
+            .filter(|name| !name.is_empty())
+            .or_else(|| self.default_profile.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    /// Profile as written in the file. A missing `DEFAULT_PROFILE` is empty.
+    pub fn get(&self, name: &str) -> Result<Profile, ValueError> {
+        match self.profiles.get(name) {
+            Some(profile) => Ok(profile.clone()),
+            None if name == DEFAULT_PROFILE => Ok(Profile::default()),
+            None => Err(ValueError {
+                message: format!(
+                    "Unknown profile {:?}, available: {}",
+                    name,
+                    self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+            }),
+        }
+    }
+
+    /// Profile with the environment applied
+    pub fn profile(&self, name: &str) -> Result<Profile, ValueError> {
+        Ok(self.get(name)?.with_env(|var| env::var(var).ok()))
+    }
+}
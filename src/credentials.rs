@@ -0,0 +1,260 @@
+use std::{
+    env,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    fs,
+    path::PathBuf,
+    process::Command,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+use crate::model::error::ValueError;
+
+/// Text that is redacted when printed and wiped from memory on drop
+///
+/// ```rust
+/// use reydenx::credentials::Secret;
+///
+/// let password = Secret::from("hunter2");
+/// assert_eq!(format!("{:?} {}", password, password), "Secret(\"***\") ***");
+/// assert_eq!(password.expose(), "hunter2");
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    /// The actual value, keep it out of logs
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Secret").field(&"***").finish()
+    }
+}
+
+impl Display for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "***")
+    }
+}
+
+/// Serialized as the plain value so tokens and configs round-trip
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: Secret,
+}
+
+/// Source of the login, asked every time the client authenticates
+pub trait CredentialProvider: Send + Sync {
+    fn credentials(&self) -> Result<Credentials, Box<dyn Error>>;
+}
+
+impl<P: CredentialProvider + ?Sized> CredentialProvider for Box<P> {
+    fn credentials(&self) -> Result<Credentials, Box<dyn Error>> {
+        (**self).credentials()
+    }
+}
+
+/// Credentials known up front
+#[derive(Debug, Clone)]
+pub struct StaticCredentials(Credentials);
+
+impl StaticCredentials {
+    pub fn new(username: String, password: Secret) -> Self {
+        Self(Credentials { username, password })
+    }
+}
+
+impl CredentialProvider for StaticCredentials {
+    fn credentials(&self) -> Result<Credentials, Box<dyn Error>> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Credentials from environment variables, `REYDENX_EMAIL` and `REYDENX_PASSWORD` by default
+///
+/// ```rust
+/// use reydenx::credentials::{CredentialProvider, EnvCredentials};
+///
+/// std::env::set_var("MY_EMAIL", "me@example.com");
+/// std::env::set_var("MY_PASSWORD", "hunter2");
+/// let res = EnvCredentials::vars("MY_EMAIL", "MY_PASSWORD").credentials().unwrap();
+/// assert_eq!(res.password.expose(), "hunter2");
+///
+/// assert!(EnvCredentials::vars("MY_EMAIL", "NOT_SET").credentials().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    username_var: String,
+    password_var: String,
+}
+
+impl Default for EnvCredentials {
+    fn default() -> Self {
+        Self::vars("REYDENX_EMAIL", "REYDENX_PASSWORD")
+    }
+}
+
+impl EnvCredentials {
+    pub fn vars(username_var: &str, password_var: &str) -> Self {
+        Self {
+            username_var: username_var.to_string(),
+            password_var: password_var.to_string(),
+        }
+    }
+}
+
+impl CredentialProvider for EnvCredentials {
+    fn credentials(&self) -> Result<Credentials, Box<dyn Error>> {
+        let var = |name: &str| {
+            env::var(name).map_err(|_| ValueError {
+                message: format!("Environment variable {} is not set", name),
+            })
+        };
+        Ok(Credentials {
+            username: var(&self.username_var)?,
+            password: Secret::new(var(&self.password_var)?),
+        })
+    }
+}
+
+/// Password from the first line of a file, like a Docker or Kubernetes secret
+#[derive(Debug, Clone)]
+pub struct FileCredentials {
+    username: String,
+    path: PathBuf,
+}
+
+impl FileCredentials {
+    pub fn new(username: String, path: impl Into<PathBuf>) -> Self {
+        Self {
+            username,
+            path: path.into(),
+        }
+    }
+}
+
+impl CredentialProvider for FileCredentials {
+    fn credentials(&self) -> Result<Credentials, Box<dyn Error>> {
+        let text = Secret::new(fs::read_to_string(&self.path)?);
+        Ok(Credentials {
+            username: self.username.clone(),
+            password: first_line(text.expose()).ok_or_else(|| ValueError {
+                message: format!("Password file {} is empty", self.path.display()),
+            })?,
+        })
+    }
+}
+
+/// Password printed by a shell command, like `pass show reyden-x`
+///
+/// ```rust
+/// use reydenx::credentials::{CommandCredentials, CredentialProvider};
+///
+/// let provider = CommandCredentials::new(String::from("me@example.com"), "echo hunter2");
+/// assert_eq!(provider.credentials().unwrap().password.expose(), "hunter2");
+///
+/// let binary = CommandCredentials::new(String::from("me@example.com"), "printf 'pw\\377'");
+/// let error = binary.credentials().unwrap_err().to_string();
+/// assert!(error.contains("invalid UTF-8") && !error.contains("pw"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CommandCredentials {
+    username: String,
+    command: String,
+}
+
+impl CommandCredentials {
+    pub fn new(username: String, command: &str) -> Self {
+        Self {
+            username,
+            command: command.to_string(),
+        }
+    }
+}
+
+impl CredentialProvider for CommandCredentials {
+    fn credentials(&self) -> Result<Credentials, Box<dyn Error>> {
+        Ok(Credentials {
+            username: self.username.clone(),
+            password: run_command(&self.command)?,
+        })
+    }
+}
+
+/// First line a shell command prints, like the password from `pass show reyden-x`
+pub(crate) fn run_command(command: &str) -> Result<Secret, Box<dyn Error>> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()?
+    } else {
+        Command::new("sh").args(["-c", command]).output()?
+    };
+    if !output.status.success() {
+        return Err(Box::new(ValueError {
+            message: format!("Credentials command failed with {}", output.status),
+        }));
+    }
+    let stdout = Secret::new(String::from_utf8(output.stdout).map_err(|e| {
+        e.into_bytes().zeroize();
+        ValueError {
+            message: String::from("Credentials command printed invalid UTF-8"),
+        }
+    })?);
+    first_line(stdout.expose()).ok_or_else(|| {
+        Box::new(ValueError {
+            message: String::from("Credentials command printed nothing"),
+        }) as Box<dyn Error>
+    })
+}
+
+fn first_line(text: &str) -> Option<Secret> {
+    text.lines()
+        .next()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(Secret::from)
+}
@@ -0,0 +1,597 @@
+use std::{error::Error, io::Write, marker::PhantomData};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    client::Requests,
+    model::{
+        error::ValueError,
+        order::{DateAndQuantity, IdAndQuantity, OnlineStats, Order, Payment, SiteStats},
+        price::Price,
+        price_category::PriceCategory,
+        result::{ActionResult, Pages},
+        task::TaskStatus,
+        traffic::Traffic,
+        user::{Balance, User},
+    },
+    orders,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    JsonLines,
+}
+
+/// Model with a flat list of named columns.
+/// Column names and their order are stable across versions.
+pub trait Record {
+    fn columns() -> &'static [&'static str];
+
+    /// Values in the order of `columns()`
+    fn values(&self) -> Vec<Value>;
+}
+
+impl Record for Order {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "created_at",
+            "updated_at",
+            "uuid",
+            "status",
+            "ordered_view_qty",
+            "price_per_view",
+            "is_autostart",
+            "online_users_limit",
+            "platform",
+            "content_type",
+            "parameters_launch_mode",
+            "parameters_work_mode",
+            "parameters_delay",
+            "parameters_delay_time",
+            "parameters_even_distribution",
+            "parameters_even_distribution_time",
+            "statistics_active_time_in_seconds",
+            "statistics_views",
+            "statistics_clicks",
+            "statistics_ctr",
+            "statistics_average_online_in_settings",
+            "statistics_average_online_in_fact",
+            "statistics_average_session_in_seconds",
+            "content_classification_labels",
+        ]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        let p = &self.parameters;
+        let s = self.statistics.as_ref();
+        vec![
+            json!(self.id),
+            json!(self.created_at),
+            json!(self.updated_at),
+            json!(self.uuid),
+            json!(self.status),
+            json!(self.ordered_view_qty),
+            json!(self.price_per_view),
+            json!(self.is_autostart),
+            json!(self.online_users_limit),
+            json!(self.platform),
+            json!(self.content_type),
+            json!(p.launch_mode),
+            json!(p.work_mode),
+            json!(p.delay),
+            json!(p.delay_time),
+            json!(p.even_distribution),
+            json!(p.even_distribution_time),
+            json!(s.map(|s| s.active_time_in_seconds)),
+            json!(s.map(|s| s.views)),
+            json!(s.map(|s| s.clicks)),
+            json!(s.map(|s| s.ctr)),
+            json!(s.map(|s| s.average.online.in_settings)),
+            json!(s.map(|s| s.average.online.in_fact)),
+            json!(s.map(|s| s.average.session_in_seconds)),
+            json!(self
+                .content_classification_labels
+                .as_ref()
+                .map(|l| l.join(";"))),
+        ]
+    }
+}
+
+impl Record for OnlineStats {
+    fn columns() -> &'static [&'static str] {
+        &["created_at", "in_settings", "in_fact"]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            json!(self.created_at),
+            json!(self.in_settings),
+            json!(self.in_fact),
+        ]
+    }
+}
+
+impl Record for DateAndQuantity {
+    fn columns() -> &'static [&'static str] {
+        &["date", "quantity"]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![json!(self.date), json!(self.quantity)]
+    }
+}
+
+impl Record for SiteStats {
+    fn columns() -> &'static [&'static str] {
+        &["domain", "views", "clicks", "ctr"]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            json!(self.domain),
+            json!(self.views),
+            json!(self.clicks),
+            json!(self.ctr),
+        ]
+    }
+}
+
+impl Record for Payment {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "created_at",
+            "updated_at",
+            "payed_at",
+            "amount",
+            "external_id",
+            "uuid",
+            "receipt",
+        ]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            json!(self.id),
+            json!(self.created_at),
+            json!(self.updated_at),
+            json!(self.payed_at),
+            json!(self.amount),
+            json!(self.external_id),
+            json!(self.uuid),
+            json!(self.receipt),
+        ]
+    }
+}
+
+impl Record for Traffic {
+    fn columns() -> &'static [&'static str] {
+        &["code", "quantity"]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![json!(self.code), json!(self.quantity)]
+    }
+}
+
+impl Record for IdAndQuantity {
+    fn columns() -> &'static [&'static str] {
+        &["id", "quantity"]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![json!(self.id), json!(self.quantity)]
+    }
+}
+
+impl Record for Price {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "name",
+            "format",
+            "price",
+            "description",
+            "views_min",
+            "views_max",
+            "views_step",
+            "online_viewers_min",
+            "online_viewers_max",
+            "online_viewers_step",
+            "category_id",
+        ]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            json!(self.id),
+            json!(self.name),
+            json!(self.format),
+            json!(self.price),
+            json!(self.description),
+            json!(self.views.min),
+            json!(self.views.max),
+            json!(self.views.step),
+            json!(self.online_viewers.min),
+            json!(self.online_viewers.max),
+            json!(self.online_viewers.step),
+            json!(self.category_id),
+        ]
+    }
+}
+
+impl Record for PriceCategory {
+    fn columns() -> &'static [&'static str] {
+        &["id", "is_active", "name", "description"]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            json!(self.id),
+            json!(self.is_active),
+            json!(self.name),
+            json!(self.description),
+        ]
+    }
+}
+
+impl Record for Balance {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "amount",
+            "currency_id",
+            "user_id",
+            "formatted_amount",
+            "currency",
+        ]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            json!(self.id),
+            json!(self.amount),
+            json!(self.currency_id),
+            json!(self.user_id),
+            json!(self.formatted_amount),
+            json!(self.currency),
+        ]
+    }
+}
+
+impl Record for User {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "username",
+            "date_joined",
+            "email",
+            "is_active",
+            "is_blocked",
+            "has_image",
+            "image_url",
+            "currency_id",
+            "discount_value",
+            "is_reseller",
+            "twitch_id",
+            "twitch_login",
+        ]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            json!(self.id),
+            json!(self.username),
+            json!(self.date_joined),
+            json!(self.email),
+            json!(self.is_active),
+            json!(self.is_blocked),
+            json!(self.has_image),
+            json!(self.image_url),
+            json!(self.currency_id),
+            json!(self.discount_value),
+            json!(self.is_reseller),
+            json!(self.twitch_id),
+            json!(self.twitch_login),
+        ]
+    }
+}
+
+impl Record for ActionResult {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "request_id",
+            "order_id",
+            "action",
+            "value",
+            "task_id",
+            "task_url",
+            "task_expires_at",
+        ]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            json!(self.request_id),
+            json!(self.order_id),
+            json!(self.action),
+            json!(self.value),
+            json!(self.task.id),
+            json!(self.task.url),
+            json!(self.task.expires_at),
+        ]
+    }
+}
+
+impl Record for TaskStatus {
+    fn columns() -> &'static [&'static str] {
+        &["status"]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![json!(self.status)]
+    }
+}
+
+/// Text of a value in a CSV cell, null is an empty cell
+pub fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Writes records as CSV or JSON Lines
+///
+/// ```rust
+/// use reydenx::{
+///     export::{Exporter, Format},
+///     model::traffic::Traffic,
+/// };
+///
+/// let mut exporter = Exporter::<_, Traffic>::new(Vec::new(), Format::Csv);
+/// exporter
+///     .write(&Traffic { code: String::from("DE"), quantity: 42 })
+///     .unwrap();
+/// let out = String::from_utf8(exporter.finish().unwrap()).unwrap();
+/// assert_eq!(out, "code,quantity\nDE,42\n");
+/// ```
+pub struct Exporter<W: Write, R: Record> {
+    format: Format,
+    columns: Vec<usize>,
+    csv: Option<csv::Writer<W>>,
+    writer: Option<W>,
+    header_written: bool,
+    record: PhantomData<R>,
+}
+
+impl<W: Write, R: Record> Exporter<W, R> {
+    /// Exporter of all columns
+    pub fn new(writer: W, format: Format) -> Self {
+        let (csv, writer) = match format {
+            Format::Csv => (
+                Some(
+                    csv::WriterBuilder::new()
+                        .has_headers(false)
+                        .from_writer(writer),
+                ),
+                None,
+            ),
+            Format::JsonLines => (None, Some(writer)),
+        };
+        Self {
+            format,
+            columns: (0..R::columns().len()).collect(),
+            csv,
+            writer,
+            header_written: false,
+            record: PhantomData,
+        }
+    }
+
+    /// Export only the given columns, in the given order
+    pub fn select(mut self, names: &[&str]) -> Result<Self, ValueError> {
+        self.columns = names
+            .iter()
+            .map(|name| {
+                R::columns()
+                    .iter()
+                    .position(|c| c == name)
+                    .ok_or_else(|| ValueError {
+                        message: format!(
+                            "Unknown column {:?}, available: {}",
+                            name,
+                            R::columns().join(", ")
+                        ),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    pub fn write(&mut self, record: &R) -> Result<(), Box<dyn Error>> {
+        let values = record.values();
+        match self.format {
+            Format::Csv => {
+                self.write_header()?;
+                let csv = self.csv.as_mut().unwrap();
+                csv.write_record(self.columns.iter().map(|i| cell(&values[*i])))?;
+            }
+            Format::JsonLines => {
+                let mut row = Map::new();
+                for i in &self.columns {
+                    row.insert(R::columns()[*i].to_string(), values[*i].clone());
+                }
+                let writer = self.writer.as_mut().unwrap();
+                serde_json::to_writer(&mut *writer, &row)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_all<'a>(
+        &mut self,
+        records: impl IntoIterator<Item = &'a R>,
+    ) -> Result<usize, Box<dyn Error>>
+    where
+        R: 'a,
+    {
+        let mut count = 0;
+        for record in records {
+            self.write(record)?;
+            count += 1;
+        }
+        self.flush()?;
+        Ok(count)
+    }
+
+    /// CSV header is written even when there are no records
+    fn write_header(&mut self) -> Result<(), Box<dyn Error>> {
+        if let (Some(csv), false) = (self.csv.as_mut(), self.header_written) {
+            csv.write_record(self.columns.iter().map(|i| R::columns()[*i]))?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.format {
+            Format::Csv => {
+                self.write_header()?;
+                self.csv.as_mut().unwrap().flush()?
+            }
+            Format::JsonLines => self.writer.as_mut().unwrap().flush()?,
+        }
+        Ok(())
+    }
+
+    /// Flush and return the underlying writer
+    pub fn finish(mut self) -> Result<W, Box<dyn Error>> {
+        self.flush()?;
+        match self.format {
+            Format::Csv => Ok(self.csv.take().unwrap().into_inner().map_err(|e| {
+                Box::new(ValueError {
+                    message: e.to_string(),
+                })
+            })?),
+            Format::JsonLines => Ok(self.writer.take().unwrap()),
+        }
+    }
+}
+
+fn exporter<W: Write, R: Record>(
+    writer: W,
+    format: Format,
+    columns: Option<&[&str]>,
+) -> Result<Exporter<W, R>, Box<dyn Error>> {
+    let exporter = Exporter::new(writer, format);
+    match columns {
+        Some(names) => Ok(exporter.select(names)?),
+        None => Ok(exporter),
+    }
+}
+
+/// Export all orders page by page, returns the number of written records
+///
+/// ```rust,no_run
+/// use std::io::stdout;
+///
+/// use reydenx::{
+///     client::{Auth, Client},
+///     export::{export_orders, Format},
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         let res = export_orders(
+///             client,
+///             stdout(),
+///             Format::Csv,
+///             Some(&["id", "status", "statistics_views"]),
+///         );
+///         println!("{:#?}", res);
+///     }
+/// }
+/// ```
+pub fn export_orders(
+    c: &impl Requests,
+    writer: impl Write,
+    format: Format,
+    columns: Option<&[&str]>,
+) -> Result<usize, Box<dyn Error>> {
+    let mut exporter = exporter::<_, Order>(writer, format, columns)?;
+    let mut count = 0;
+    for page in Pages::new(|cursor| orders::all_orders(c, cursor)) {
+        count += exporter.write_all(&page?)?;
+    }
+    Ok(count)
+}
+
+/// Export all payments of an order page by page
+pub fn export_payments(
+    c: &impl Requests,
+    order_id: u32,
+    writer: impl Write,
+    format: Format,
+    columns: Option<&[&str]>,
+) -> Result<usize, Box<dyn Error>> {
+    let mut exporter = exporter::<_, Payment>(writer, format, columns)?;
+    let mut count = 0;
+    for page in Pages::new(|cursor| orders::payments(c, order_id, cursor)) {
+        count += exporter.write_all(&page?)?;
+    }
+    Ok(count)
+}
+
+/// Export online statistics of an order
+pub fn export_online_stats(
+    c: &impl Requests,
+    order_id: u32,
+    writer: impl Write,
+    format: Format,
+    columns: Option<&[&str]>,
+) -> Result<usize, Box<dyn Error>> {
+    let stats = orders::online_stats(c, order_id)?.result;
+    exporter::<_, OnlineStats>(writer, format, columns)?.write_all(&stats)
+}
+
+/// Export views statistics of an order
+pub fn export_views_stats(
+    c: &impl Requests,
+    order_id: u32,
+    writer: impl Write,
+    format: Format,
+    columns: Option<&[&str]>,
+) -> Result<usize, Box<dyn Error>> {
+    let stats = orders::views_stats(c, order_id)?.result;
+    exporter::<_, DateAndQuantity>(writer, format, columns)?.write_all(&stats)
+}
+
+/// Export clicks statistics of an order
+pub fn export_clicks_stats(
+    c: &impl Requests,
+    order_id: u32,
+    writer: impl Write,
+    format: Format,
+    columns: Option<&[&str]>,
+) -> Result<usize, Box<dyn Error>> {
+    let stats = orders::clicks_stats(c, order_id)?.result;
+    exporter::<_, DateAndQuantity>(writer, format, columns)?.write_all(&stats)
+}
+
+/// Export sites statistics of an order
+pub fn export_sites_stats(
+    c: &impl Requests,
+    order_id: u32,
+    writer: impl Write,
+    format: Format,
+    columns: Option<&[&str]>,
+) -> Result<usize, Box<dyn Error>> {
+    let stats = orders::sites_stats(c, order_id)?.result;
+    exporter::<_, SiteStats>(writer, format, columns)?.write_all(&stats)
+}
@@ -0,0 +1,191 @@
+use std::error::Error;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analytics::{series, Bucket, Point},
+    client::Requests,
+    model::order::{DateAndQuantity, Order},
+    orders,
+};
+
+/// Where a running order is heading at its current pace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Forecast {
+    pub order_id: u32,
+    pub at: DateTime<Utc>,
+    pub ordered_views: u32,
+    pub delivered_views: u32,
+    pub remaining_views: u32,
+    /// Unknown until the order has delivered anything
+    pub views_per_hour: Option<f64>,
+    pub spend_per_hour: Option<f64>,
+    pub remaining_cost: f64,
+    pub hours_left: Option<f64>,
+    /// When the ordered views run out
+    pub eta: Option<DateTime<Utc>>,
+}
+
+impl Forecast {
+    /// Whether the order keeps delivering until the stream ends.
+    /// Unknown when there is no delivery rate yet.
+    pub fn lasts_until(&self, stream_end: DateTime<Utc>) -> Option<bool> {
+        if self.remaining_views == 0 {
+            return Some(stream_end <= self.at);
+        }
+        self.eta.map(|eta| eta >= stream_end)
+    }
+
+    /// Views to add so the order keeps delivering until the stream ends
+    ///
+    /// ```rust
+    /// use chrono::{Duration, TimeZone, Utc};
+    /// use reydenx::forecast::Forecast;
+    ///
+    /// let at = Utc.with_ymd_and_hms(2024, 3, 1, 18, 0, 0).unwrap();
+    /// let forecast = Forecast {
+    ///     order_id: 12345,
+    ///     at,
+    ///     ordered_views: 5000,
+    ///     delivered_views: 4000,
+    ///     remaining_views: 1000,
+    ///     views_per_hour: Some(500.0),
+    ///     spend_per_hour: Some(250.0),
+    ///     remaining_cost: 500.0,
+    ///     hours_left: Some(2.0),
+    ///     eta: Some(at + Duration::hours(2)),
+    /// };
+    /// assert_eq!(forecast.lasts_until(at + Duration::hours(3)), Some(false));
+    /// assert_eq!(forecast.views_to_add(at + Duration::hours(3)), Some(500));
+    /// ```
+    pub fn views_to_add(&self, stream_end: DateTime<Utc>) -> Option<u32> {
+        let rate = self.views_per_hour?;
+        let hours = (stream_end - self.at).num_seconds().max(0) as f64 / 3600.0;
+        let needed = (rate * hours).ceil() - self.remaining_views as f64;
+        Some(needed.max(0.0) as u32)
+    }
+}
+
+/// Views per hour over the `window` ending at `now`
+///
+/// Each sample covers a bucket up to the next sample time, an hour or a day as the
+/// spacing of samples shows. Buckets count in proportion to their overlap with the
+/// window, and the bucket in progress only up to `now`.
+///
+/// ```rust
+/// use chrono::{Duration, TimeZone, Utc};
+/// use reydenx::{forecast::views_rate, model::order::DateAndQuantity};
+///
+/// let views = |date: &str, quantity| DateAndQuantity { date: date.to_string(), quantity };
+/// let now = Utc.with_ymd_and_hms(2024, 3, 2, 12, 0, 0).unwrap();
+///
+/// let daily = [views("2024-03-01", 2400), views("2024-03-02", 600)];
+/// assert_eq!(views_rate(&daily, Duration::hours(24), now), Some(75.0));
+///
+/// let hourly = [views("2024-03-02 10:00:00", 300), views("2024-03-02 11:00:00", 100)];
+/// assert_eq!(views_rate(&hourly, Duration::hours(2), now), Some(200.0));
+/// assert_eq!(views_rate(&hourly, Duration::hours(1), now), Some(100.0));
+/// ```
+pub fn views_rate(views: &[DateAndQuantity], window: Duration, now: DateTime<Utc>) -> Option<f64> {
+    let points = series(views);
+    let width = bucket_width(&points)?;
+    let since = now - window;
+    let mut total = 0.0;
+    for p in &points {
+        let end = (p.time + width).min(now);
+        let covered = (end - p.time).num_seconds();
+        let overlap = (end - p.time.max(since)).num_seconds();
+        if covered > 0 && overlap > 0 {
+            total += p.value * overlap as f64 / covered as f64;
+        }
+    }
+    let start = points.first()?.time.max(since);
+    let hours = (now - start).num_seconds() as f64 / 3600.0;
+    (hours > 0.0 && total > 0.0).then(|| total / hours)
+}
+
+/// Smallest spacing of the samples, else a day for dates and an hour otherwise
+fn bucket_width(points: &[Point]) -> Option<Duration> {
+    let gap = points
+        .windows(2)
+        .map(|w| w[1].time - w[0].time)
+        .filter(|d| *d > Duration::zero())
+        .min();
+    if gap.is_some() {
+        return gap;
+    }
+    let first = points.first()?;
+    if Bucket::Day.floor(first.time) == first.time {
+        Some(Bucket::Day.duration())
+    } else {
+        Some(Bucket::Hour.duration())
+    }
+}
+
+/// Forecast an order from its views statistics.
+///
+/// The rate comes from `views_stats` within `window` before `now`; without samples it
+/// falls back to `Statistics.views` over `active_time_in_seconds`.
+pub fn forecast(
+    order: &Order,
+    views: &[DateAndQuantity],
+    window: Duration,
+    now: DateTime<Utc>,
+) -> Forecast {
+    let delivered = match &order.statistics {
+        Some(s) => s.views,
+        None => views.iter().map(|v| v.quantity.max(0) as u32).sum(),
+    };
+    let remaining = order.ordered_view_qty.saturating_sub(delivered);
+
+    let views_per_hour = views_rate(views, window, now).or_else(|| match &order.statistics {
+        Some(s) if s.active_time_in_seconds > 0 && s.views > 0 => {
+            Some(s.views as f64 / s.active_time_in_seconds as f64 * 3600.0)
+        }
+        _ => None,
+    });
+    let hours_left = views_per_hour.map(|rate| remaining as f64 / rate);
+
+    Forecast {
+        order_id: order.id,
+        at: now,
+        ordered_views: order.ordered_view_qty,
+        delivered_views: delivered,
+        remaining_views: remaining,
+        views_per_hour,
+        spend_per_hour: views_per_hour.map(|rate| rate * order.price_per_view),
+        remaining_cost: remaining as f64 * order.price_per_view,
+        hours_left,
+        eta: hours_left.map(|h| now + Duration::seconds((h * 3600.0) as i64)),
+    }
+}
+
+/// Fetch the order with its views statistics and forecast it
+///
+/// ```rust,no_run
+/// use chrono::{Duration, Utc};
+/// use reydenx::{
+///     client::{Auth, Client},
+///     forecast::fetch_forecast,
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         if let Ok(res) = fetch_forecast(client, 12345, Duration::hours(1)) {
+///             let stream_end = Utc::now() + Duration::hours(4);
+///             println!("{:#?} {:?}", res, res.views_to_add(stream_end));
+///         }
+///     }
+/// }
+/// ```
+pub fn fetch_forecast(
+    c: &impl Requests,
+    order_id: u32,
+    window: Duration,
+) -> Result<Forecast, Box<dyn Error>> {
+    let order = orders::order_details(c, order_id)?.result;
+    let views = orders::views_stats(c, order_id)?.result;
+    Ok(forecast(&order, &views, window, Utc::now()))
+}
@@ -1,7 +1,32 @@
 pub mod action;
+pub mod analytics;
+pub mod autopilot;
+pub mod budget;
+pub mod catalog;
 pub mod client;
+pub mod compliance;
+pub mod config;
+pub mod credentials;
+pub mod export;
+pub mod forecast;
+pub mod iso;
+pub mod lifecycle;
+pub mod manifest;
 pub mod model;
+pub mod notify;
 pub mod orders;
+pub mod price_history;
 pub mod prices;
+pub mod pricing;
+pub mod render;
+pub mod rules;
+pub mod runway;
+pub mod scheduler;
+pub mod simulator;
+pub mod site_audit;
+#[cfg(feature = "store")]
+pub mod store;
 pub mod traffic;
+pub mod traffic_profile;
 pub mod user;
+pub mod watch;
@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    client::Requests,
+    model::{error::TransitionError, order::Order},
+    orders::order_details,
+};
+
+/// Lifecycle state of an order, derived from `Order.status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderState {
+    New,
+    Running,
+    Stopped,
+    Completed,
+    Cancelled,
+    /// Status not known to this client. Every action is allowed, the API decides.
+    Unknown(String),
+}
+
+impl OrderState {
+    /// Map a raw `Order.status` value to a state
+    ///
+    /// ```rust
+    /// use reydenx::lifecycle::OrderState;
+    ///
+    /// assert_eq!(OrderState::from_status("active"), OrderState::Running);
+    /// assert_eq!(OrderState::from_status("CANCELED"), OrderState::Cancelled);
+    /// ```
+    pub fn from_status(status: &str) -> Self {
+        match status.to_lowercase().as_str() {
+            "new" | "created" | "pending" | "waiting" => OrderState::New,
+            "active" | "running" | "in_progress" | "working" => OrderState::Running,
+            "stopped" | "paused" | "on_pause" => OrderState::Stopped,
+            "completed" | "finished" | "done" => OrderState::Completed,
+            "cancelled" | "canceled" => OrderState::Cancelled,
+            _ => OrderState::Unknown(status.to_string()),
+        }
+    }
+
+    /// Completed and cancelled orders no longer deliver anything
+    pub fn is_final(&self) -> bool {
+        matches!(self, OrderState::Completed | OrderState::Cancelled)
+    }
+
+    /// Whether the action is allowed for an order in this state
+    ///
+    /// ```rust
+    /// use reydenx::lifecycle::{OrderState, Transition};
+    ///
+    /// assert!(OrderState::Stopped.allows(Transition::Run));
+    /// assert!(!OrderState::Running.allows(Transition::Run));
+    /// assert!(!OrderState::Cancelled.allows(Transition::ChangeOnline));
+    /// assert!(OrderState::Completed.allows(Transition::AddViews));
+    /// ```
+    pub fn allows(&self, transition: Transition) -> bool {
+        match self {
+            OrderState::New | OrderState::Stopped => transition != Transition::Stop,
+            OrderState::Running => transition != Transition::Run,
+            OrderState::Completed => transition == Transition::AddViews,
+            OrderState::Cancelled => false,
+            OrderState::Unknown(_) => true,
+        }
+    }
+}
+
+impl Display for OrderState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderState::New => write!(f, "new"),
+            OrderState::Running => write!(f, "running"),
+            OrderState::Stopped => write!(f, "stopped"),
+            OrderState::Completed => write!(f, "completed"),
+            OrderState::Cancelled => write!(f, "cancelled"),
+            OrderState::Unknown(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+/// Actions from `action.rs` that change an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    Run,
+    Stop,
+    Cancel,
+    ChangeOnline,
+    ChangeLaunchMode,
+    ChangeIncreaseTime,
+    EnableIncrease,
+    DisableIncrease,
+    AddViews,
+}
+
+impl Display for Transition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Transition::Run => write!(f, "run"),
+            Transition::Stop => write!(f, "stop"),
+            Transition::Cancel => write!(f, "cancel"),
+            Transition::ChangeOnline => write!(f, "change online"),
+            Transition::ChangeLaunchMode => write!(f, "change launch mode"),
+            Transition::ChangeIncreaseTime => write!(f, "change increase time"),
+            Transition::EnableIncrease => write!(f, "enable increase"),
+            Transition::DisableIncrease => write!(f, "disable increase"),
+            Transition::AddViews => write!(f, "add views"),
+        }
+    }
+}
+
+/// Check that the order allows the action
+pub fn check(order: &Order, transition: Transition) -> Result<(), TransitionError> {
+    if OrderState::from_status(&order.status).allows(transition) {
+        return Ok(());
+    }
+    Err(TransitionError {
+        order_id: order.id,
+        status: order.status.clone(),
+        action: transition.to_string(),
+    })
+}
+
+/// Orders used for transition checks, kept for `ttl`
+///
+/// Entries are dropped after every successful action on the order,
+/// so the next check always sees its new status.
+#[derive(Debug)]
+pub struct OrderCache {
+    ttl: Duration,
+    orders: Mutex<HashMap<u32, (Instant, Order)>>,
+}
+
+impl OrderCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            orders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, order_id: u32) -> Option<Order> {
+        let orders = self.orders.lock().unwrap();
+        match orders.get(&order_id) {
+            Some((fetched_at, order)) if fetched_at.elapsed() < self.ttl => Some(order.clone()),
+            _ => None,
+        }
+    }
+
+    /// Store an order, e.g. one already received from `all_orders`
+    pub fn insert(&self, order: Order) {
+        let mut orders = self.orders.lock().unwrap();
+        orders.insert(order.id, (Instant::now(), order));
+    }
+
+    pub fn remove(&self, order_id: u32) {
+        let mut orders = self.orders.lock().unwrap();
+        orders.remove(&order_id);
+    }
+}
+
+/// Check the action against the order lifecycle when the client has checks enabled
+///
+/// Uses the cached order when possible, otherwise fetches it with `order_details`.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use reydenx::{
+///     client::{Auth, Client},
+///     lifecycle::{ensure, Transition},
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"))
+///         .with_transition_checks(Duration::from_secs(30));
+///     if let Ok(client) = client.auth() {
+///         let res = ensure(client, 12345, Transition::Run);
+///         println!("{:#?}", res);
+///     }
+/// }
+/// ```
+pub fn ensure(
+    c: &impl Requests,
+    order_id: u32,
+    transition: Transition,
+) -> Result<(), Box<dyn Error>> {
+    let cache = match c.order_cache() {
+        Some(cache) => cache,
+        None => return Ok(()),
+    };
+    let order = match cache.get(order_id) {
+        Some(order) => order,
+        None => {
+            let order = order_details(c, order_id)?.result;
+            cache.insert(order.clone());
+            order
+        }
+    };
+    check(&order, transition)?;
+    Ok(())
+}
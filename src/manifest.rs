@@ -0,0 +1,652 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action,
+    client::Requests,
+    lifecycle::OrderState,
+    model::{
+        error::ValueError,
+        order::{
+            KickPayload, LaunchMode, LaunchParams, Order, SmoothGain, TwitchPayload, YouTubePayload,
+        },
+        result::{ActionResult, Pages},
+    },
+    orders,
+};
+
+/// Window an order should deliver in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Not created before this time
+    pub start: Option<DateTime<Utc>>,
+    /// Stopped after this time
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl Schedule {
+    fn started(&self, now: DateTime<Utc>) -> bool {
+        self.start.is_none_or(|start| now >= start)
+    }
+
+    fn ended(&self, now: DateTime<Utc>) -> bool {
+        self.end.is_some_and(|end| now >= end)
+    }
+}
+
+fn auto() -> String {
+    String::from("auto")
+}
+
+/// Desired order, identified by `name` across runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderSpec {
+    pub name: String,
+    /// `twitch`, `youtube` or `kick`
+    pub platform: String,
+    /// Numeric channel id for Twitch, channel URL otherwise
+    pub channel: String,
+    pub price_id: u32,
+    pub viewers: u32,
+    pub views: u32,
+    #[serde(default = "auto")]
+    pub launch_mode: String,
+    /// Minutes, for the delay launch mode
+    #[serde(default)]
+    pub delay_time: u8,
+    /// Minutes of smooth gain, only used when the order is created.
+    /// A live order with a different value is reported in `Plan::warnings`.
+    #[serde(default)]
+    pub smooth_gain: u32,
+    pub schedule: Option<Schedule>,
+}
+
+impl OrderSpec {
+    fn validate(&self) -> Result<(), ValueError> {
+        let error = |message: String| {
+            Err(ValueError {
+                message: format!("{}: {}", self.name, message),
+            })
+        };
+        match self.platform.to_lowercase().as_str() {
+            "twitch" if self.channel.parse::<u32>().is_err() => {
+                return error(format!(
+                    "Twitch channel must be a numeric id, got {:?}",
+                    self.channel
+                ))
+            }
+            "twitch" | "youtube" | "kick" => {}
+            _ => {
+                return error(format!(
+                    "orders can only be created for twitch, youtube and kick, got {:?}",
+                    self.platform
+                ))
+            }
+        }
+        if let Err(e) = self.launch_mode.parse::<LaunchMode>() {
+            return error(e.message);
+        }
+        Ok(())
+    }
+
+    fn smooth_gain(&self) -> SmoothGain {
+        SmoothGain {
+            enabled: self.smooth_gain > 0,
+            minutes: self.smooth_gain,
+        }
+    }
+
+    /// Create the order
+    pub fn create(&self, c: &impl Requests) -> Result<ActionResult, Box<dyn Error>> {
+        match self.platform.to_lowercase().as_str() {
+            "twitch" => orders::create_twitch_stream(
+                c,
+                &TwitchPayload {
+                    price_id: self.price_id,
+                    number_of_views: self.views,
+                    number_of_viewers: self.viewers,
+                    launch_mode: self.launch_mode.to_lowercase(),
+                    smooth_gain: self.smooth_gain(),
+                    delay_time: self.delay_time as u32,
+                    twitch_id: self.channel.parse()?,
+                    fixed_allocation: 0,
+                    on_overflow: false,
+                },
+            ),
+            "youtube" => orders::create_youtube_stream(
+                c,
+                &YouTubePayload {
+                    price_id: self.price_id,
+                    number_of_views: self.views,
+                    number_of_viewers: self.viewers,
+                    launch_mode: self.launch_mode.to_lowercase(),
+                    smooth_gain: self.smooth_gain(),
+                    delay_time: self.delay_time as u32,
+                    channel_url: self.channel.clone(),
+                    fixed_allocation: 0,
+                    on_overflow: false,
+                },
+            ),
+            "kick" => orders::create_kick_stream(
+                c,
+                &KickPayload {
+                    price_id: self.price_id,
+                    number_of_views: self.views,
+                    number_of_viewers: self.viewers,
+                    launch_mode: self.launch_mode.to_lowercase(),
+                    smooth_gain: self.smooth_gain(),
+                    delay_time: self.delay_time as u32,
+                    channel_url: self.channel.clone(),
+                    fixed_allocation: 0,
+                    on_overflow: false,
+                },
+            ),
+            _ => Err(Box::new(ValueError {
+                message: format!("Unknown platform {:?}", self.platform),
+            })),
+        }
+    }
+}
+
+/// Orders that should exist, written as TOML or YAML
+///
+/// ```rust
+/// use reydenx::manifest::Manifest;
+///
+/// let manifest = Manifest::from_toml(r#"
+///     [[order]]
+///     name = "evening-stream"
+///     platform = "twitch"
+///     channel = "123456"
+///     price_id = 1
+///     viewers = 200
+///     views = 5000
+///     launch_mode = "delay"
+///     delay_time = 15
+///     smooth_gain = 30
+///     schedule = { start = "2024-03-01T18:00:00Z", end = "2024-03-01T23:00:00Z" }
+/// "#).unwrap();
+/// assert_eq!(manifest.orders[0].viewers, 200);
+///
+/// assert!(Manifest::from_toml("[[order]]\nname = \"a\"\nplatform = \"trovo\"\nchannel = \"x\"\nprice_id = 1\nviewers = 1\nviews = 1").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default, rename = "order", alias = "orders")]
+    pub orders: Vec<OrderSpec>,
+}
+
+impl Manifest {
+    pub fn from_toml(text: &str) -> Result<Self, Box<dyn Error>> {
+        let manifest: Self = toml::from_str(text)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// ```rust
+    /// use reydenx::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_yaml(r#"
+    /// orders:
+    ///   - name: main
+    ///     platform: youtube
+    ///     channel: https://www.youtube.com/@someone
+    ///     price_id: 3
+    ///     viewers: 150
+    ///     views: 3000
+    ///     schedule:
+    ///       end: 2024-03-01T23:00:00Z
+    /// "#).unwrap();
+    /// assert!(manifest.orders[0].schedule.as_ref().unwrap().start.is_none());
+    /// ```
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(text: &str) -> Result<Self, Box<dyn Error>> {
+        let manifest: Self = serde_yaml::from_str(text)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// YAML for `.yaml` and `.yml` files with the `yaml` feature, TOML otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Self::from_yaml(&text),
+            #[cfg(not(feature = "yaml"))]
+            Some("yaml" | "yml") => Err(Box::new(ValueError {
+                message: String::from("YAML manifests need the yaml feature"),
+            })),
+            _ => Self::from_toml(&text),
+        }
+    }
+
+    fn validate(&self) -> Result<(), ValueError> {
+        let mut names = BTreeSet::new();
+        for spec in &self.orders {
+            if !names.insert(spec.name.as_str()) {
+                return Err(ValueError {
+                    message: format!("Order {:?} is defined twice", spec.name),
+                });
+            }
+            spec.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Order ids created for manifest entries, kept between runs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    pub orders: BTreeMap<String, u32>,
+}
+
+impl State {
+    /// Empty if the file does not exist yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Stop tracking an entry so the next plan creates a new order for it
+    pub fn forget(&mut self, name: &str) -> Option<u32> {
+        self.orders.remove(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Step {
+    Create {
+        spec: Box<OrderSpec>,
+    },
+    ChangeOnline {
+        name: String,
+        order_id: u32,
+        from: u32,
+        to: u32,
+    },
+    ChangeLaunchMode {
+        name: String,
+        order_id: u32,
+        mode: String,
+        delay_time: u8,
+    },
+    AddViews {
+        name: String,
+        order_id: u32,
+        views: u32,
+    },
+    Stop {
+        name: String,
+        order_id: u32,
+        reason: String,
+    },
+    /// Drop the state entry of an order that left the manifest
+    Forget {
+        name: String,
+        order_id: u32,
+    },
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Create { spec } => write!(
+                f,
+                "+ create {}: {} {}, {} viewers, {} views, {} launch",
+                spec.name, spec.platform, spec.channel, spec.viewers, spec.views, spec.launch_mode
+            ),
+            Step::ChangeOnline {
+                name,
+                order_id,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ change online of {} (#{}): {} -> {}",
+                name, order_id, from, to
+            ),
+            Step::ChangeLaunchMode {
+                name,
+                order_id,
+                mode,
+                delay_time,
+            } => {
+                write!(
+                    f,
+                    "~ change launch mode of {} (#{}) to {}",
+                    name, order_id, mode
+                )?;
+                if mode.eq_ignore_ascii_case("delay") {
+                    write!(f, " {} min", delay_time)?;
+                }
+                Ok(())
+            }
+            Step::AddViews {
+                name,
+                order_id,
+                views,
+            } => write!(f, "~ add {} views to {} (#{})", views, name, order_id),
+            Step::Stop {
+                name,
+                order_id,
+                reason,
+            } => write!(f, "- stop {} (#{}): {}", name, order_id, reason),
+            Step::Forget { name, order_id } => write!(
+                f,
+                "- forget {} (#{}) in the state file, it left the manifest",
+                name, order_id
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub steps: Vec<Step>,
+    /// Differences that cannot be reconciled by an action
+    pub warnings: Vec<String>,
+    /// Problems that must be resolved before the plan can be applied
+    pub blockers: Vec<String>,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        !self.blockers.is_empty()
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.steps.is_empty() {
+            writeln!(f, "No changes")?;
+        }
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "! {}", warning)?;
+        }
+        for blocker in &self.blockers {
+            writeln!(f, "x {}", blocker)?;
+        }
+        Ok(())
+    }
+}
+
+/// Steps that bring the live `orders` in line with the manifest
+///
+/// ```rust
+/// use chrono::{TimeZone, Utc};
+/// use reydenx::{
+///     manifest::{plan, Manifest, State, Step},
+///     model::order::Order,
+/// };
+///
+/// let manifest = Manifest::from_toml(r#"
+///     [[order]]
+///     name = "main"
+///     platform = "twitch"
+///     channel = "123456"
+///     price_id = 1
+///     viewers = 300
+///     views = 6000
+///     smooth_gain = 30
+///
+///     [[order]]
+///     name = "late-show"
+///     platform = "kick"
+///     channel = "https://kick.com/someone"
+///     price_id = 2
+///     viewers = 100
+///     views = 1000
+///     schedule = { start = "2024-03-02T20:00:00Z" }
+/// "#).unwrap();
+///
+/// let order = |id: u32, status: &str, limit: u32, views: u32| -> Order {
+///     serde_json::from_value(serde_json::json!({
+///         "id": id, "created_at": "", "updated_at": "", "uuid": "", "status": status,
+///         "ordered_view_qty": views, "price_per_view": 0.5, "is_autostart": false,
+///         "online_users_limit": limit, "platform": "twitch", "content_type": "stream",
+///         "parameters": { "launch_mode": "auto", "work_mode": "", "delay": false,
+///             "delay_time": 0, "even_distribution": false, "even_distribution_time": 0 },
+///         "statistics": null, "content_classification_labels": null
+///     })).unwrap()
+/// };
+/// let live = vec![order(1, "active", 200, 5000), order(2, "active", 50, 500)];
+/// let mut state = State::default();
+/// state.orders.insert(String::from("main"), 1);
+/// state.orders.insert(String::from("old"), 2);
+///
+/// let now = Utc.with_ymd_and_hms(2024, 3, 1, 18, 0, 0).unwrap();
+/// let res = plan(&manifest, &state, &live, now);
+/// assert_eq!(res.steps.len(), 4);
+/// assert!(matches!(res.steps[0], Step::ChangeOnline { order_id: 1, from: 200, to: 300, .. }));
+/// assert!(matches!(res.steps[1], Step::AddViews { order_id: 1, views: 1000, .. }));
+/// assert!(matches!(res.steps[2], Step::Stop { order_id: 2, .. }));
+/// assert!(matches!(res.steps[3], Step::Forget { order_id: 2, .. }));
+/// assert!(res.warnings.iter().any(|w| w.contains("smooth gain")));
+///
+/// // the scheduled order is created once its window opens
+/// let later = Utc.with_ymd_and_hms(2024, 3, 2, 20, 0, 0).unwrap();
+/// let res = plan(&manifest, &state, &live, later);
+/// assert!(res.steps.iter().any(|s| matches!(s, Step::Create { spec } if spec.name == "late-show")));
+/// print!("{}", res);
+///
+/// // an order that disappeared is not replaced until it is forgotten
+/// let res = plan(&manifest, &state, &live[1..], now);
+/// assert!(res.is_blocked());
+/// assert!(!res.steps.iter().any(|s| matches!(s, Step::Create { .. })));
+/// state.forget("main");
+/// let res = plan(&manifest, &state, &live[1..], now);
+/// assert!(!res.is_blocked());
+/// assert!(matches!(&res.steps[0], Step::Create { spec } if spec.name == "main"));
+/// ```
+pub fn plan(manifest: &Manifest, state: &State, orders: &[Order], now: DateTime<Utc>) -> Plan {
+    let live: BTreeMap<u32, &Order> = orders.iter().map(|o| (o.id, o)).collect();
+    let mut res = Plan::default();
+
+    for spec in &manifest.orders {
+        let schedule = spec.schedule.clone().unwrap_or(Schedule {
+            start: None,
+            end: None,
+        });
+        let tracked = state.orders.get(&spec.name).copied();
+        let order = match tracked.and_then(|id| live.get(&id)) {
+            Some(order) => *order,
+            None => {
+                if let Some(id) = tracked {
+                    res.blockers.push(format!(
+                        "{}: order #{} is no longer listed, forget it to create a new one",
+                        spec.name, id
+                    ));
+                    continue;
+                }
+                if schedule.started(now) && !schedule.ended(now) {
+                    res.steps.push(Step::Create {
+                        spec: Box::new(spec.clone()),
+                    });
+                }
+                continue;
+            }
+        };
+
+        let state = OrderState::from_status(&order.status);
+        if state.is_final() {
+            res.warnings.push(format!(
+                "{}: order #{} is {}, forget it to order again",
+                spec.name, order.id, order.status
+            ));
+            continue;
+        }
+        if schedule.ended(now) {
+            if state != OrderState::Stopped {
+                res.steps.push(Step::Stop {
+                    name: spec.name.clone(),
+                    order_id: order.id,
+                    reason: String::from("schedule ended"),
+                });
+            }
+            continue;
+        }
+        if !order.platform.eq_ignore_ascii_case(&spec.platform) {
+            res.warnings.push(format!(
+                "{}: order #{} is on {}, changing the platform needs a new order",
+                spec.name, order.id, order.platform
+            ));
+        }
+        let p = &order.parameters;
+        let smooth_gain = if p.even_distribution {
+            p.even_distribution_time
+        } else {
+            0
+        };
+        if smooth_gain != spec.smooth_gain {
+            res.warnings.push(format!(
+                "{}: order #{} has {} minutes of smooth gain, changing it needs a new order",
+                spec.name, order.id, smooth_gain
+            ));
+        }
+
+        if order.online_users_limit != spec.viewers {
+            res.steps.push(Step::ChangeOnline {
+                name: spec.name.clone(),
+                order_id: order.id,
+                from: order.online_users_limit,
+                to: spec.viewers,
+            });
+        }
+        let changed = if spec.launch_mode.eq_ignore_ascii_case("delay") {
+            !(p.delay || p.launch_mode.eq_ignore_ascii_case("delay"))
+                || p.delay_time != spec.delay_time as u32
+        } else {
+            !p.launch_mode.eq_ignore_ascii_case(&spec.launch_mode)
+        };
+        if changed {
+            res.steps.push(Step::ChangeLaunchMode {
+                name: spec.name.clone(),
+                order_id: order.id,
+                mode: spec.launch_mode.to_lowercase(),
+                delay_time: spec.delay_time,
+            });
+        }
+        match spec.views.cmp(&order.ordered_view_qty) {
+            std::cmp::Ordering::Greater => res.steps.push(Step::AddViews {
+                name: spec.name.clone(),
+                order_id: order.id,
+                views: spec.views - order.ordered_view_qty,
+            }),
+            std::cmp::Ordering::Less => res.warnings.push(format!(
+                "{}: order #{} already has {} views, views cannot be lowered",
+                spec.name, order.id, order.ordered_view_qty
+            )),
+            std::cmp::Ordering::Equal => {}
+        }
+    }
+
+    let names: BTreeSet<&str> = manifest.orders.iter().map(|s| s.name.as_str()).collect();
+    for (name, id) in &state.orders {
+        if names.contains(name.as_str()) {
+            continue;
+        }
+        let running = live.get(id).is_some_and(|o| {
+            let state = OrderState::from_status(&o.status);
+            !state.is_final() && state != OrderState::Stopped
+        });
+        if running {
+            res.steps.push(Step::Stop {
+                name: name.clone(),
+                order_id: *id,
+                reason: String::from("not in the manifest"),
+            });
+        }
+        res.steps.push(Step::Forget {
+            name: name.clone(),
+            order_id: *id,
+        });
+    }
+    res
+}
+
+/// Plan against every order of the account
+pub fn fetch_plan(
+    c: &impl Requests,
+    manifest: &Manifest,
+    state: &State,
+    now: DateTime<Utc>,
+) -> Result<Plan, Box<dyn Error>> {
+    let mut live = Vec::new();
+    for page in Pages::new(|cursor| orders::all_orders(c, cursor)) {
+        live.extend(page?);
+    }
+    Ok(plan(manifest, state, &live, now))
+}
+
+/// Execute the steps in order, stopping at the first error. Blocked plans are refused.
+/// `state` is updated after every step, save it even when this fails.
+pub fn apply(
+    c: &impl Requests,
+    plan: &Plan,
+    state: &mut State,
+    mut on_step: impl FnMut(&Step, Option<&ActionResult>),
+) -> Result<(), Box<dyn Error>> {
+    if plan.is_blocked() {
+        return Err(Box::new(ValueError {
+            message: format!("Plan is blocked: {}", plan.blockers.join("; ")),
+        }));
+    }
+    for step in &plan.steps {
+        let res = match step {
+            Step::Create { spec } => {
+                let res = spec.create(c)?;
+                state.orders.insert(spec.name.clone(), res.order_id);
+                res
+            }
+            Step::ChangeOnline { order_id, to, .. } => action::change_online(c, *order_id, *to)?,
+            Step::ChangeLaunchMode {
+                order_id,
+                mode,
+                delay_time,
+                ..
+            } => action::change_launch_mode(
+                c,
+                *order_id,
+                &LaunchParams {
+                    mode: mode.parse()?,
+                    delay_time: *delay_time,
+                },
+            )?,
+            Step::AddViews {
+                order_id, views, ..
+            } => action::add_views(c, *order_id, *views)?,
+            Step::Stop { order_id, .. } => action::stop(c, *order_id)?,
+            Step::Forget { name, .. } => {
+                state.orders.remove(name);
+                on_step(step, None);
+                continue;
+            }
+        };
+        on_step(step, Some(&res));
+    }
+    Ok(())
+}
@@ -43,3 +43,41 @@ impl ser::Error for ValueError {
         }
     }
 }
+
+#[derive(Debug)]
+pub struct TransitionError {
+    pub order_id: u32,
+    pub status: String,
+    pub action: String,
+}
+
+impl Display for TransitionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Order {} with status {:?} does not allow action {:?}",
+            self.order_id, self.status, self.action
+        )
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+#[derive(Debug)]
+pub struct BudgetError {
+    pub estimate: f64,
+    pub reasons: Vec<String>,
+}
+
+impl Display for BudgetError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Order blocked by budget guard (estimated cost {:.2}): {}",
+            self.estimate,
+            self.reasons.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for BudgetError {}
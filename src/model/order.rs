@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 use serde::{
     ser::{Error, SerializeStruct},
@@ -7,7 +7,7 @@ use serde::{
 
 use super::error::ValueError;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub enum LaunchMode {
     Auto,
     Manual,
@@ -37,7 +37,22 @@ impl fmt::Display for LaunchMode {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl FromStr for LaunchMode {
+    type Err = ValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(LaunchMode::Auto),
+            "manual" => Ok(LaunchMode::Manual),
+            "delay" => Ok(LaunchMode::Delay),
+            _ => Err(ValueError {
+                message: format!("Unknown launch mode {:?}", s),
+            }),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Parameters {
     pub launch_mode: String,
     pub work_mode: String,
@@ -47,19 +62,19 @@ pub struct Parameters {
     pub even_distribution_time: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AvgValues {
     pub in_settings: f64,
     pub in_fact: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Average {
     pub online: AvgValues,
     pub session_in_seconds: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Statistics {
     pub active_time_in_seconds: u32,
     pub views: u32,
@@ -68,7 +83,7 @@ pub struct Statistics {
     pub average: Average,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Order {
     pub id: u32,
     pub created_at: String,
@@ -86,26 +101,26 @@ pub struct Order {
     pub content_classification_labels: Option<Vec<String>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OnlineStats {
     pub created_at: String,
     pub in_settings: f64,
     pub in_fact: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DateAndQuantity {
     pub date: String,
     pub quantity: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct IdAndQuantity {
     pub id: u32,
     pub quantity: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SiteStats {
     pub domain: String,
     pub views: i32,
@@ -113,7 +128,7 @@ pub struct SiteStats {
     pub ctr: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Payment {
     pub id: u32,
     pub created_at: String,
@@ -125,13 +140,13 @@ pub struct Payment {
     pub receipt: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SmoothGain {
     pub enabled: bool,
     pub minutes: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TwitchPayload {
     pub price_id: u32,
     pub number_of_views: u32,
@@ -144,7 +159,7 @@ pub struct TwitchPayload {
     pub on_overflow: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct YouTubePayload {
     pub price_id: u32,
     pub number_of_views: u32,
@@ -157,7 +172,7 @@ pub struct YouTubePayload {
     pub on_overflow: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct KickPayload {
     pub price_id: u32,
     pub number_of_views: u32,
@@ -170,12 +185,105 @@ pub struct KickPayload {
     pub on_overflow: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Fields shared by all order creation payloads
+pub trait OrderPayload {
+    fn price_id(&self) -> u32;
+
+    fn number_of_views(&self) -> u32;
+
+    fn number_of_viewers(&self) -> u32;
+
+    fn launch_mode(&self) -> &str;
+
+    fn smooth_gain(&self) -> &SmoothGain;
+
+    fn delay_time(&self) -> u32;
+}
+
+impl OrderPayload for TwitchPayload {
+    fn price_id(&self) -> u32 {
+        self.price_id
+    }
+
+    fn number_of_views(&self) -> u32 {
+        self.number_of_views
+    }
+
+    fn number_of_viewers(&self) -> u32 {
+        self.number_of_viewers
+    }
+
+    fn launch_mode(&self) -> &str {
+        &self.launch_mode
+    }
+
+    fn smooth_gain(&self) -> &SmoothGain {
+        &self.smooth_gain
+    }
+
+    fn delay_time(&self) -> u32 {
+        self.delay_time
+    }
+}
+
+impl OrderPayload for YouTubePayload {
+    fn price_id(&self) -> u32 {
+        self.price_id
+    }
+
+    fn number_of_views(&self) -> u32 {
+        self.number_of_views
+    }
+
+    fn number_of_viewers(&self) -> u32 {
+        self.number_of_viewers
+    }
+
+    fn launch_mode(&self) -> &str {
+        &self.launch_mode
+    }
+
+    fn smooth_gain(&self) -> &SmoothGain {
+        &self.smooth_gain
+    }
+
+    fn delay_time(&self) -> u32 {
+        self.delay_time
+    }
+}
+
+impl OrderPayload for KickPayload {
+    fn price_id(&self) -> u32 {
+        self.price_id
+    }
+
+    fn number_of_views(&self) -> u32 {
+        self.number_of_views
+    }
+
+    fn number_of_viewers(&self) -> u32 {
+        self.number_of_viewers
+    }
+
+    fn launch_mode(&self) -> &str {
+        &self.launch_mode
+    }
+
+    fn smooth_gain(&self) -> &SmoothGain {
+        &self.smooth_gain
+    }
+
+    fn delay_time(&self) -> u32 {
+        self.delay_time
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Identifiers {
     pub identifiers: Vec<u32>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct LaunchParams {
     pub mode: LaunchMode,
     pub delay_time: u8,
@@ -190,12 +298,11 @@ impl Serialize for LaunchParams {
         match self.mode {
             LaunchMode::Delay => {
                 if self.delay_time < 5 || self.delay_time > 240 {
-                    return Err(ValueError {
+                    return Err(S::Error::custom(ValueError {
                         message: String::from(
                             "The number of minutes for delayed start should be from 5 to 240",
                         ),
-                    })
-                    .map_err(S::Error::custom);
+                    }));
                 }
                 s.serialize_field("delay_time", &self.delay_time)?;
             }
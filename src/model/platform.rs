@@ -1,6 +1,11 @@
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
 
-#[derive(Debug)]
+use super::error::ValueError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Platform {
     Twitch,
     YouTube,
@@ -10,6 +15,17 @@ pub enum Platform {
     Kick,
 }
 
+impl Platform {
+    pub const ALL: [Platform; 6] = [
+        Platform::Twitch,
+        Platform::YouTube,
+        Platform::GoodGame,
+        Platform::Trovo,
+        Platform::VkPlay,
+        Platform::Kick,
+    ];
+}
+
 impl Display for Platform {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -22,3 +38,21 @@ impl Display for Platform {
         }
     }
 }
+
+impl FromStr for Platform {
+    type Err = ValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "twitch" => Ok(Platform::Twitch),
+            "youtube" => Ok(Platform::YouTube),
+            "goodgame" => Ok(Platform::GoodGame),
+            "trovo" => Ok(Platform::Trovo),
+            "vkplay" => Ok(Platform::VkPlay),
+            "kick" => Ok(Platform::Kick),
+            _ => Err(ValueError {
+                message: format!("Unknown platform {:?}", s),
+            }),
+        }
+    }
+}
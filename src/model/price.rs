@@ -1,13 +1,56 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MinMaxStep {
     pub min: u32,
     pub max: u32,
     pub step: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl MinMaxStep {
+    /// Whether the value is within limits and on a step from `min`
+    ///
+    /// ```rust
+    /// use reydenx::model::price::MinMaxStep;
+    ///
+    /// let limits = MinMaxStep { min: 10, max: 500, step: 10 };
+    /// assert!(limits.allows(300));
+    /// assert!(!limits.allows(305));
+    /// assert!(!limits.allows(510));
+    /// ```
+    pub fn allows(&self, value: u32) -> bool {
+        if value < self.min || value > self.max {
+            return false;
+        }
+        self.step == 0 || (value - self.min).is_multiple_of(self.step)
+    }
+
+    /// Nearest allowed value
+    ///
+    /// ```rust
+    /// use reydenx::model::price::MinMaxStep;
+    ///
+    /// let limits = MinMaxStep { min: 10, max: 500, step: 20 };
+    /// assert_eq!(limits.snap(5), 10);
+    /// assert_eq!(limits.snap(41), 50);
+    /// assert_eq!(limits.snap(900), 490);
+    /// ```
+    pub fn snap(&self, value: u32) -> u32 {
+        let value = value.clamp(self.min, self.max.max(self.min));
+        if self.step == 0 {
+            return value;
+        }
+        let steps = ((value - self.min) as f64 / self.step as f64).round() as u32;
+        let snapped = self.min + steps * self.step;
+        if snapped > self.max {
+            snapped - self.step
+        } else {
+            snapped
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Price {
     pub id: u32,
     pub name: String,
@@ -1,6 +1,8 @@
+use std::error::Error;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StandardResult<T> {
     pub request_id: String,
     pub cached: bool,
@@ -11,21 +13,82 @@ pub struct StandardResult<T> {
 
 impl<T> StandardResult<T> {
     pub fn has_next(&self) -> bool {
-        match &self.cursor {
-            Some(_) => true,
-            None => false,
+        self.cursor.is_some()
+    }
+}
+
+/// Iterator over cursor pages of a list endpoint, stops after the first error
+///
+/// ```rust,no_run
+/// use reydenx::{
+///     client::{Auth, Client},
+///     model::result::Pages,
+///     orders::all_orders,
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         for page in Pages::new(|cursor| all_orders(client, cursor)) {
+///             println!("{:#?}", page);
+///         }
+///     }
+/// }
+/// ```
+pub struct Pages<T, F>
+where
+    F: FnMut(Option<String>) -> Result<StandardResult<Vec<T>>, Box<dyn Error>>,
+{
+    fetch: F,
+    cursor: Option<String>,
+    done: bool,
+}
+
+impl<T, F> Pages<T, F>
+where
+    F: FnMut(Option<String>) -> Result<StandardResult<Vec<T>>, Box<dyn Error>>,
+{
+    pub fn new(fetch: F) -> Self {
+        Self {
+            fetch,
+            cursor: None,
+            done: false,
+        }
+    }
+}
+
+impl<T, F> Iterator for Pages<T, F>
+where
+    F: FnMut(Option<String>) -> Result<StandardResult<Vec<T>>, Box<dyn Error>>,
+{
+    type Item = Result<Vec<T>, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match (self.fetch)(self.cursor.take()) {
+            Ok(res) => {
+                self.done = !res.has_next();
+                self.cursor = res.cursor;
+                Some(Ok(res.result))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Task {
     pub id: String,
     pub url: String,
     pub expires_at: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ActionResult {
     pub request_id: String,
     pub order_id: u32,
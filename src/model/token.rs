@@ -1,9 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::credentials::Secret;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Token {
-    pub access_token: String,
+    pub access_token: Secret,
     pub expires_in: String,
 }
 
@@ -0,0 +1,699 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analytics::parse_time,
+    lifecycle::OrderState,
+    model::{
+        error::ResponseError,
+        order::{OnlineStats, Order},
+        task::TaskStatus,
+        user::Balance,
+    },
+    watch::Event,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+/// Something about an order or the account worth telling someone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Alert {
+    LowBalance {
+        amount: u32,
+        currency: String,
+        threshold: u32,
+    },
+    OrderFinished {
+        order_id: u32,
+        status: String,
+    },
+    TaskFailed {
+        order_id: u32,
+        task_id: String,
+        status: String,
+    },
+    /// Delivered viewers of the latest sample below tolerance
+    DeliveryShortfall {
+        order_id: u32,
+        in_settings: f64,
+        in_fact: f64,
+        at: String,
+    },
+}
+
+impl Alert {
+    /// Alert when the balance is below `threshold`
+    pub fn low_balance(balance: &Balance, threshold: u32) -> Option<Self> {
+        (balance.amount < threshold).then(|| Alert::LowBalance {
+            amount: balance.amount,
+            currency: balance.currency.clone(),
+            threshold,
+        })
+    }
+
+    /// Alert when the order is completed or cancelled
+    pub fn order_finished(order: &Order) -> Option<Self> {
+        OrderState::from_status(&order.status)
+            .is_final()
+            .then(|| Alert::OrderFinished {
+                order_id: order.id,
+                status: order.status.clone(),
+            })
+    }
+
+    /// Alert when the task status reports a failure
+    pub fn task_failed(order_id: u32, task_id: &str, task: &TaskStatus) -> Option<Self> {
+        let status = task.status.to_lowercase();
+        (status.contains("fail") || status.contains("error")).then(|| Alert::TaskFailed {
+            order_id,
+            task_id: task_id.to_string(),
+            status: task.status.clone(),
+        })
+    }
+
+    /// Alert when the latest online sample delivers less than `1 - tolerance` of the ordered viewers
+    pub fn delivery_shortfall(
+        order_id: u32,
+        online: &[OnlineStats],
+        tolerance: f64,
+    ) -> Option<Self> {
+        let latest = online
+            .iter()
+            .filter_map(|s| parse_time(&s.created_at).map(|t| (t, s)))
+            .max_by_key(|(t, _)| *t)?
+            .1;
+        (latest.in_settings > 0.0 && latest.in_fact < latest.in_settings * (1.0 - tolerance)).then(
+            || Alert::DeliveryShortfall {
+                order_id,
+                in_settings: latest.in_settings,
+                in_fact: latest.in_fact,
+                at: latest.created_at.clone(),
+            },
+        )
+    }
+
+    /// Alert for a watcher event, e.g. an order moving to a final status
+    pub fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::StatusChanged { order_id, to, .. } if OrderState::from_status(to).is_final() => {
+                Some(Alert::OrderFinished {
+                    order_id: *order_id,
+                    status: to.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Alert::LowBalance { .. } => "low_balance",
+            Alert::OrderFinished { .. } => "order_finished",
+            Alert::TaskFailed { .. } => "task_failed",
+            Alert::DeliveryShortfall { .. } => "delivery_shortfall",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Alert::LowBalance { .. } | Alert::DeliveryShortfall { .. } => Severity::Warning,
+            Alert::OrderFinished { .. } => Severity::Info,
+            Alert::TaskFailed { .. } => Severity::Critical,
+        }
+    }
+
+    /// Alerts with the same key are duplicates
+    pub fn key(&self) -> String {
+        match self {
+            Alert::LowBalance { .. } => String::from("low_balance"),
+            Alert::OrderFinished { order_id, .. } => format!("order_finished:{}", order_id),
+            Alert::TaskFailed {
+                order_id, task_id, ..
+            } => format!("task_failed:{}:{}", order_id, task_id),
+            Alert::DeliveryShortfall { order_id, .. } => {
+                format!("delivery_shortfall:{}", order_id)
+            }
+        }
+    }
+
+    /// Values available to templates
+    pub fn vars(&self) -> BTreeMap<&'static str, String> {
+        let mut vars = BTreeMap::new();
+        vars.insert("kind", self.kind().to_string());
+        vars.insert("severity", self.severity().to_string());
+        match self {
+            Alert::LowBalance {
+                amount,
+                currency,
+                threshold,
+            } => {
+                vars.insert("amount", amount.to_string());
+                vars.insert("currency", currency.clone());
+                vars.insert("threshold", threshold.to_string());
+            }
+            Alert::OrderFinished { order_id, status } => {
+                vars.insert("order_id", order_id.to_string());
+                vars.insert("status", status.clone());
+            }
+            Alert::TaskFailed {
+                order_id,
+                task_id,
+                status,
+            } => {
+                vars.insert("order_id", order_id.to_string());
+                vars.insert("task_id", task_id.clone());
+                vars.insert("status", status.clone());
+            }
+            Alert::DeliveryShortfall {
+                order_id,
+                in_settings,
+                in_fact,
+                at,
+            } => {
+                vars.insert("order_id", order_id.to_string());
+                vars.insert("in_settings", format!("{:.0}", in_settings));
+                vars.insert("in_fact", format!("{:.0}", in_fact));
+                vars.insert("percent", format!("{:.1}", in_fact / in_settings * 100.0));
+                vars.insert("at", at.clone());
+            }
+        }
+        vars
+    }
+}
+
+/// Title and body with `{name}` placeholders filled from `Alert::vars`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    pub title: String,
+    pub body: String,
+}
+
+impl Template {
+    pub fn new(title: &str, body: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Built-in template of an alert kind
+    pub fn default_for(kind: &str) -> Self {
+        match kind {
+            "low_balance" => Self::new(
+                "Low balance",
+                "Balance is {amount} {currency}, below {threshold} {currency}",
+            ),
+            "order_finished" => {
+                Self::new("Order {order_id} finished", "Order {order_id} is {status}")
+            }
+            "task_failed" => Self::new(
+                "Task failed for order {order_id}",
+                "Task {task_id} of order {order_id} ended with status {status}",
+            ),
+            "delivery_shortfall" => Self::new(
+                "Delivery shortfall on order {order_id}",
+                "{in_fact} of {in_settings} viewers online ({percent}%) at {at}",
+            ),
+            _ => Self::new("{kind}", "{kind}"),
+        }
+    }
+}
+
+/// Replace `{name}` placeholders, unknown names are kept as they are
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+///
+/// use reydenx::notify::render;
+///
+/// let vars = BTreeMap::from([("order_id", String::from("12345"))]);
+/// assert_eq!(render("Order {order_id} {missing}", &vars), "Order 12345 {missing}");
+/// ```
+pub fn render(template: &str, vars: &BTreeMap<&'static str, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let name = &after[..end];
+                match vars.get(name) {
+                    Some(value) => out.push_str(value),
+                    None => {
+                        out.push('{');
+                        out.push_str(name);
+                        out.push('}');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Rendered alert as delivered to sinks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub kind: String,
+    pub key: String,
+    pub severity: Severity,
+    pub title: String,
+    pub body: String,
+    pub at: DateTime<Utc>,
+    pub alert: Alert,
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}: {}",
+            self.at.to_rfc3339(),
+            self.severity,
+            self.title,
+            self.body
+        )
+    }
+}
+
+pub trait NotificationSink {
+    fn send(&self, message: &Message) -> Result<(), Box<dyn Error>>;
+}
+
+impl<F> NotificationSink for F
+where
+    F: Fn(&Message) -> Result<(), Box<dyn Error>>,
+{
+    fn send(&self, message: &Message) -> Result<(), Box<dyn Error>> {
+        self(message)
+    }
+}
+
+/// Prints messages as single lines
+#[derive(Debug, Clone, Default)]
+pub struct StdoutSink;
+
+impl NotificationSink for StdoutSink {
+    fn send(&self, message: &Message) -> Result<(), Box<dyn Error>> {
+        println!("{}", message);
+        Ok(())
+    }
+}
+
+/// Appends messages to a file as JSON lines
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    pub path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl NotificationSink for FileSink {
+    fn send(&self, message: &Message) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(message)?)?;
+        Ok(())
+    }
+}
+
+/// Posts messages as JSON to a URL
+///
+/// ```rust
+/// use std::{
+///     io::{Read, Write},
+///     net::TcpListener,
+///     thread,
+/// };
+///
+/// use reydenx::notify::{Alert, Notifier, Severity, WebhookSink};
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let url = format!("http://{}/hook", listener.local_addr().unwrap());
+/// let server = thread::spawn(move || {
+///     let (mut stream, _) = listener.accept().unwrap();
+///     let mut request = Vec::new();
+///     let mut buf = [0; 4096];
+///     while !String::from_utf8_lossy(&request).contains("\"alert\"") {
+///         let n = stream.read(&mut buf).unwrap();
+///         request.extend_from_slice(&buf[..n]);
+///     }
+///     stream
+///         .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+///         .unwrap();
+///     String::from_utf8(request).unwrap()
+/// });
+///
+/// let notifier = Notifier::new().route(WebhookSink::new(&url), Severity::Info);
+/// let alert = Alert::OrderFinished { order_id: 12345, status: String::from("completed") };
+/// notifier.notify(&alert).unwrap();
+///
+/// let request = server.join().unwrap();
+/// assert!(request.starts_with("POST /hook"));
+/// assert!(request.contains("Order 12345 finished"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    client: reqwest::blocking::Client,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: reqwest::blocking::Client::new(),
+            url: url.to_string(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Extra header, e.g. for authorization
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl NotificationSink for WebhookSink {
+    fn send(&self, message: &Message) -> Result<(), Box<dyn Error>> {
+        let mut req = self
+            .client
+            .post(&self.url)
+            .json(message)
+            .timeout(Duration::new(5, 0));
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+        let resp = req.send()?;
+        if !resp.status().is_success() {
+            return Err(Box::new(ResponseError {
+                status: resp.status(),
+                message: format!("Webhook {} failed", self.url),
+            }));
+        }
+        Ok(())
+    }
+}
+
+/// What happened to an alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// Number of sinks the message was sent to
+    Sent(usize),
+    /// Same key already sent within the dedup window
+    Duplicate,
+    RateLimited,
+}
+
+struct Route {
+    sink: Box<dyn NotificationSink + Send + Sync>,
+    min_severity: Severity,
+    kinds: Option<Vec<String>>,
+}
+
+/// Renders alerts and routes them to sinks, with deduplication and rate limiting
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+///
+/// use chrono::Duration;
+/// use reydenx::notify::{Alert, Delivery, Message, Notifier, Severity};
+///
+/// let received: Arc<Mutex<Vec<String>>> = Arc::default();
+/// let inbox = received.clone();
+/// let notifier = Notifier::new()
+///     .route(
+///         move |m: &Message| {
+///             inbox.lock().unwrap().push(m.body.clone());
+///             Ok(())
+///         },
+///         Severity::Warning,
+///     )
+///     .template("low_balance", "Top up", "Only {amount} {currency} left")
+///     .dedup(Duration::hours(1));
+///
+/// let alert = Alert::LowBalance { amount: 40, currency: String::from("USD"), threshold: 100 };
+/// assert_eq!(notifier.notify(&alert).unwrap(), Delivery::Sent(1));
+/// assert_eq!(notifier.notify(&alert).unwrap(), Delivery::Duplicate);
+///
+/// let finished = Alert::OrderFinished { order_id: 12345, status: String::from("completed") };
+/// assert_eq!(notifier.notify(&finished).unwrap(), Delivery::Sent(0));
+/// assert_eq!(*received.lock().unwrap(), vec![String::from("Only 40 USD left")]);
+/// ```
+pub struct Notifier {
+    routes: Vec<Route>,
+    templates: HashMap<String, Template>,
+    dedup: chrono::Duration,
+    rate_limit: Option<(usize, chrono::Duration)>,
+    history: Mutex<History>,
+}
+
+/// Sent keys and send times, behind one lock
+#[derive(Default)]
+struct History {
+    sent: HashMap<String, DateTime<Utc>>,
+    recent: VecDeque<DateTime<Utc>>,
+}
+
+impl fmt::Debug for Notifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Notifier")
+            .field("routes", &self.routes.len())
+            .field("templates", &self.templates)
+            .field("dedup", &self.dedup)
+            .field("rate_limit", &self.rate_limit)
+            .finish()
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Notifier {
+    /// No sinks, duplicates suppressed for 30 minutes, no rate limit
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            templates: HashMap::new(),
+            dedup: chrono::Duration::minutes(30),
+            rate_limit: None,
+            history: Mutex::new(History::default()),
+        }
+    }
+
+    /// Send alerts of at least `min_severity` to the sink
+    pub fn route(
+        mut self,
+        sink: impl NotificationSink + Send + Sync + 'static,
+        min_severity: Severity,
+    ) -> Self {
+        self.routes.push(Route {
+            sink: Box::new(sink),
+            min_severity,
+            kinds: None,
+        });
+        self
+    }
+
+    /// Send only alerts of the given kinds to the sink, e.g. `["task_failed"]`
+    pub fn route_kinds(
+        mut self,
+        sink: impl NotificationSink + Send + Sync + 'static,
+        kinds: &[&str],
+    ) -> Self {
+        self.routes.push(Route {
+            sink: Box::new(sink),
+            min_severity: Severity::Info,
+            kinds: Some(kinds.iter().map(|k| k.to_string()).collect()),
+        });
+        self
+    }
+
+    /// Replace the built-in template of an alert kind
+    pub fn template(mut self, kind: &str, title: &str, body: &str) -> Self {
+        self.templates
+            .insert(kind.to_string(), Template::new(title, body));
+        self
+    }
+
+    /// Suppress alerts with the same key within `window`
+    pub fn dedup(mut self, window: chrono::Duration) -> Self {
+        self.dedup = window;
+        self
+    }
+
+    /// Send at most `max` messages per `period`, the rest is dropped
+    pub fn rate_limit(mut self, max: usize, period: chrono::Duration) -> Self {
+        self.rate_limit = Some((max, period));
+        self
+    }
+
+    pub fn message(&self, alert: &Alert, at: DateTime<Utc>) -> Message {
+        let template = self
+            .templates
+            .get(alert.kind())
+            .cloned()
+            .unwrap_or_else(|| Template::default_for(alert.kind()));
+        let vars = alert.vars();
+        Message {
+            kind: alert.kind().to_string(),
+            key: alert.key(),
+            severity: alert.severity(),
+            title: render(&template.title, &vars),
+            body: render(&template.body, &vars),
+            at,
+            alert: alert.clone(),
+        }
+    }
+
+    pub fn notify(&self, alert: &Alert) -> Result<Delivery, Box<dyn Error>> {
+        self.notify_at(alert, Utc::now())
+    }
+
+    /// Send the alert as of `now`. Every sink is tried, the first error is returned.
+    ///
+    /// Alerts no route wants are not counted for deduplication or the rate limit.
+    /// When a sink fails the alert is not marked as sent, so the next call tries every
+    /// sink again. Its rate slot is only given back when no sink accepted it.
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// use chrono::{Duration, TimeZone, Utc};
+    /// use reydenx::notify::{Alert, Delivery, Message, Notifier, Severity};
+    ///
+    /// static DOWN: AtomicBool = AtomicBool::new(true);
+    /// let notifier = Notifier::new()
+    ///     .route(
+    ///         |_: &Message| match DOWN.load(Ordering::SeqCst) {
+    ///             true => Err("sink is down".into()),
+    ///             false => Ok(()),
+    ///         },
+    ///         Severity::Warning,
+    ///     )
+    ///     .route(|_: &Message| Ok(()), Severity::Warning)
+    ///     .rate_limit(2, Duration::hours(1));
+    ///
+    /// let now = Utc.with_ymd_and_hms(2024, 3, 1, 18, 0, 0).unwrap();
+    /// let finished = Alert::OrderFinished { order_id: 12345, status: String::from("completed") };
+    /// assert_eq!(notifier.notify_at(&finished, now).unwrap(), Delivery::Sent(0));
+    ///
+    /// let alert = Alert::LowBalance { amount: 40, currency: String::from("USD"), threshold: 100 };
+    /// // one sink failed, so the alert is not a duplicate yet
+    /// assert!(notifier.notify_at(&alert, now).is_err());
+    /// DOWN.store(false, Ordering::SeqCst);
+    /// assert_eq!(notifier.notify_at(&alert, now).unwrap(), Delivery::Sent(2));
+    /// assert_eq!(notifier.notify_at(&alert, now).unwrap(), Delivery::Duplicate);
+    ///
+    /// let failed = Alert::TaskFailed {
+    ///     order_id: 12345,
+    ///     task_id: String::from("task-1"),
+    ///     status: String::from("error"),
+    /// };
+    /// assert_eq!(notifier.notify_at(&failed, now).unwrap(), Delivery::RateLimited);
+    /// ```
+    pub fn notify_at(&self, alert: &Alert, now: DateTime<Utc>) -> Result<Delivery, Box<dyn Error>> {
+        let routes: Vec<&Route> = self
+            .routes
+            .iter()
+            .filter(|route| match &route.kinds {
+                Some(kinds) => kinds.iter().any(|k| k == alert.kind()),
+                None => alert.severity() >= route.min_severity,
+            })
+            .collect();
+        if routes.is_empty() {
+            return Ok(Delivery::Sent(0));
+        }
+
+        // reserve the key and a rate slot together, so concurrent calls can't both send
+        let key = alert.key();
+        {
+            let mut history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+            history.sent.retain(|_, at| now - *at < self.dedup);
+            if history.sent.contains_key(&key) {
+                return Ok(Delivery::Duplicate);
+            }
+            if let Some((max, period)) = self.rate_limit {
+                while history.recent.front().is_some_and(|at| now - *at >= period) {
+                    history.recent.pop_front();
+                }
+                if history.recent.len() >= max {
+                    return Ok(Delivery::RateLimited);
+                }
+                history.recent.push_back(now);
+            }
+            history.sent.insert(key.clone(), now);
+        }
+
+        let message = self.message(alert, now);
+        let mut count = 0;
+        let mut first_error = None;
+        for route in routes {
+            match route.sink.send(&message) {
+                Ok(()) => count += 1,
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if first_error.is_some() {
+            // sent again next time, the rate slot is kept if any sink got the message
+            let mut history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+            if history.sent.get(&key) == Some(&now) {
+                history.sent.remove(&key);
+            }
+            if count == 0 && self.rate_limit.is_some() {
+                if let Some(i) = history.recent.iter().rposition(|at| *at == now) {
+                    history.recent.remove(i);
+                }
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(Delivery::Sent(count)),
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::Requests,
+    model::{
+        platform::Platform,
+        price::{MinMaxStep, Price},
+        price_category::PriceCategory,
+    },
+    prices::{get_categories, get_prices},
+};
+
+/// Prices and categories at a point in time, keyed by platform name
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PriceSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub prices: BTreeMap<String, Vec<Price>>,
+    pub categories: Vec<PriceCategory>,
+}
+
+impl PriceSnapshot {
+    /// Fetch current prices of the given platforms and all categories
+    ///
+    /// ```rust,no_run
+    /// use reydenx::{
+    ///     client::{Auth, Client},
+    ///     model::platform::Platform,
+    ///     price_history::PriceSnapshot,
+    /// };
+    ///
+    /// fn main() {
+    ///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+    ///     if let Ok(client) = client.auth() {
+    ///         let previous = PriceSnapshot::load("prices.json").unwrap();
+    ///         let current = PriceSnapshot::take(client, &Platform::ALL).unwrap();
+    ///         println!("{}", previous.diff(&current));
+    ///         current.save("prices.json").unwrap();
+    ///     }
+    /// }
+    /// ```
+    pub fn take(c: &impl Requests, platforms: &[Platform]) -> Result<Self, Box<dyn Error>> {
+        let mut prices = BTreeMap::new();
+        for platform in platforms {
+            prices.insert(platform.to_string(), get_prices(c, *platform)?.result);
+        }
+        Ok(Self {
+            taken_at: Utc::now(),
+            prices,
+            categories: get_categories(c)?.result,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let data = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Changes from this snapshot to a newer one
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    ///
+    /// use chrono::{TimeZone, Utc};
+    /// use reydenx::{
+    ///     model::price::{MinMaxStep, Price},
+    ///     price_history::PriceSnapshot,
+    /// };
+    ///
+    /// let price = |id, price| Price {
+    ///     id,
+    ///     name: format!("Price {}", id),
+    ///     format: String::from("views"),
+    ///     price,
+    ///     description: String::new(),
+    ///     views: MinMaxStep { min: 100, max: 100000, step: 100 },
+    ///     online_viewers: MinMaxStep { min: 10, max: 1000, step: 10 },
+    ///     category_id: 1,
+    /// };
+    /// let snapshot = |day, prices| PriceSnapshot {
+    ///     taken_at: Utc.with_ymd_and_hms(2024, 5, day, 0, 0, 0).unwrap(),
+    ///     prices: BTreeMap::from([(String::from("twitch"), prices)]),
+    ///     categories: Vec::new(),
+    /// };
+    /// let old = snapshot(1, vec![price(1, 0.5), price(2, 0.4)]);
+    /// let new = snapshot(2, vec![price(1, 0.6), price(3, 0.2)]);
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.added[0].id, 3);
+    /// assert_eq!(diff.removed[0].id, 2);
+    /// assert_eq!(diff.changed[0].price, Some((0.5, 0.6)));
+    /// assert_eq!(
+    ///     diff.to_string(),
+    ///     "Price changes from 2024-05-01 00:00:00 UTC to 2024-05-02 00:00:00 UTC\n\
+    ///      + [twitch] #3 Price 3: 0.2\n\
+    ///      - [twitch] #2 Price 2: 0.4\n\
+    ///      ~ [twitch] #1 Price 1\n    price: 0.5 -> 0.6 (+20.00%)\n"
+    /// );
+    /// assert!(old.diff(&old).is_empty());
+    /// ```
+    pub fn diff(&self, newer: &PriceSnapshot) -> PriceDiff {
+        let mut diff = PriceDiff {
+            from: self.taken_at,
+            to: newer.taken_at,
+            ..Default::default()
+        };
+
+        let platforms: Vec<&String> = self
+            .prices
+            .keys()
+            .chain(
+                newer
+                    .prices
+                    .keys()
+                    .filter(|p| !self.prices.contains_key(*p)),
+            )
+            .collect();
+        for platform in platforms {
+            let old = index(self.prices.get(platform));
+            let new = index(newer.prices.get(platform));
+            for (id, price) in &old {
+                match new.get(id) {
+                    Some(current) => {
+                        if let Some(change) = compare(platform, price, current) {
+                            diff.changed.push(change);
+                        }
+                    }
+                    None => diff.removed.push(PriceEntry::new(platform, price)),
+                }
+            }
+            for (id, price) in &new {
+                if !old.contains_key(id) {
+                    diff.added.push(PriceEntry::new(platform, price));
+                }
+            }
+        }
+
+        for category in &newer.categories {
+            match self.categories.iter().find(|c| c.id == category.id) {
+                Some(old) if old.is_active != category.is_active || old.name != category.name => {
+                    diff.changed_categories.push(CategoryChange {
+                        id: category.id,
+                        name: category.name.clone(),
+                        was_active: old.is_active,
+                        is_active: category.is_active,
+                    });
+                }
+                Some(_) => {}
+                None => diff.added_categories.push(category.clone()),
+            }
+        }
+        for category in &self.categories {
+            if !newer.categories.iter().any(|c| c.id == category.id) {
+                diff.removed_categories.push(category.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+fn index(prices: Option<&Vec<Price>>) -> BTreeMap<u32, &Price> {
+    prices
+        .map(|p| p.iter().map(|price| (price.id, price)).collect())
+        .unwrap_or_default()
+}
+
+fn compare(platform: &str, old: &Price, new: &Price) -> Option<PriceChange> {
+    let change = PriceChange {
+        platform: platform.to_string(),
+        id: new.id,
+        name: new.name.clone(),
+        price: (old.price != new.price).then_some((old.price, new.price)),
+        format: (old.format != new.format).then(|| (old.format.clone(), new.format.clone())),
+        views: LimitChange::between(&old.views, &new.views),
+        online_viewers: LimitChange::between(&old.online_viewers, &new.online_viewers),
+        category: (old.category_id != new.category_id)
+            .then_some((old.category_id, new.category_id)),
+    };
+    if change.price.is_none()
+        && change.format.is_none()
+        && change.views.is_none()
+        && change.online_viewers.is_none()
+        && change.category.is_none()
+    {
+        return None;
+    }
+    Some(change)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PriceEntry {
+    pub platform: String,
+    pub id: u32,
+    pub name: String,
+    pub price: f64,
+}
+
+impl PriceEntry {
+    fn new(platform: &str, price: &Price) -> Self {
+        Self {
+            platform: platform.to_string(),
+            id: price.id,
+            name: price.name.clone(),
+            price: price.price,
+        }
+    }
+}
+
+/// Old and new `MinMaxStep` values
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LimitChange {
+    pub min: (u32, u32),
+    pub max: (u32, u32),
+    pub step: (u32, u32),
+}
+
+impl LimitChange {
+    fn between(old: &MinMaxStep, new: &MinMaxStep) -> Option<Self> {
+        if old.min == new.min && old.max == new.max && old.step == new.step {
+            return None;
+        }
+        Some(Self {
+            min: (old.min, new.min),
+            max: (old.max, new.max),
+            step: (old.step, new.step),
+        })
+    }
+}
+
+impl Display for LimitChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}..{} step {} -> {}..{} step {}",
+            self.min.0, self.max.0, self.step.0, self.min.1, self.max.1, self.step.1
+        )
+    }
+}
+
+/// Changed fields of a price, as old and new values
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PriceChange {
+    pub platform: String,
+    pub id: u32,
+    pub name: String,
+    pub price: Option<(f64, f64)>,
+    pub format: Option<(String, String)>,
+    pub views: Option<LimitChange>,
+    pub online_viewers: Option<LimitChange>,
+    pub category: Option<(u32, u32)>,
+}
+
+impl PriceChange {
+    /// Difference between new and old price
+    pub fn price_delta(&self) -> Option<f64> {
+        self.price.map(|(old, new)| new - old)
+    }
+
+    /// Price change in percent of the old price
+    pub fn price_delta_percent(&self) -> Option<f64> {
+        match self.price {
+            Some((old, new)) if old != 0.0 => Some((new - old) / old * 100.0),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CategoryChange {
+    pub id: u32,
+    pub name: String,
+    pub was_active: bool,
+    pub is_active: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PriceDiff {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub added: Vec<PriceEntry>,
+    pub removed: Vec<PriceEntry>,
+    pub changed: Vec<PriceChange>,
+    pub added_categories: Vec<PriceCategory>,
+    pub removed_categories: Vec<PriceCategory>,
+    pub changed_categories: Vec<CategoryChange>,
+}
+
+impl PriceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.added_categories.is_empty()
+            && self.removed_categories.is_empty()
+            && self.changed_categories.is_empty()
+    }
+}
+
+impl Display for PriceDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Price changes from {} to {}", self.from, self.to)?;
+        if self.is_empty() {
+            return write!(f, "No changes");
+        }
+        for p in &self.added {
+            writeln!(f, "+ [{}] #{} {}: {}", p.platform, p.id, p.name, p.price)?;
+        }
+        for p in &self.removed {
+            writeln!(f, "- [{}] #{} {}: {}", p.platform, p.id, p.name, p.price)?;
+        }
+        for c in &self.changed {
+            writeln!(f, "~ [{}] #{} {}", c.platform, c.id, c.name)?;
+            if let Some((old, new)) = c.price {
+                write!(f, "    price: {} -> {}", old, new)?;
+                match c.price_delta_percent() {
+                    Some(percent) => writeln!(f, " ({:+.2}%)", percent)?,
+                    None => writeln!(f)?,
+                }
+            }
+            if let Some((old, new)) = &c.format {
+                writeln!(f, "    format: {} -> {}", old, new)?;
+            }
+            if let Some(limits) = &c.views {
+                writeln!(f, "    views: {}", limits)?;
+            }
+            if let Some(limits) = &c.online_viewers {
+                writeln!(f, "    online viewers: {}", limits)?;
+            }
+            if let Some((old, new)) = c.category {
+                writeln!(f, "    category: {} -> {}", old, new)?;
+            }
+        }
+        for c in &self.added_categories {
+            writeln!(f, "+ category #{} {}", c.id, c.name)?;
+        }
+        for c in &self.removed_categories {
+            writeln!(f, "- category #{} {}", c.id, c.name)?;
+        }
+        for c in &self.changed_categories {
+            writeln!(
+                f,
+                "~ category #{} {}: active {} -> {}",
+                c.id, c.name, c.was_active, c.is_active
+            )?;
+        }
+        Ok(())
+    }
+}
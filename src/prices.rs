@@ -20,7 +20,6 @@ use crate::{
 ///         String::from("EMAIL"),
 ///         String::from("PASSWORD"),
 ///     );
-
 ///     if let Ok(client) = client.auth() {
 ///         let res = get_prices(client, Platform::Twitch);
 ///         println!("{:#?}", res);
@@ -48,7 +47,6 @@ pub fn get_prices(
 ///         String::from("EMAIL"),
 ///         String::from("PASSWORD"),
 ///     );
-
 ///     if let Ok(client) = client.auth() {
 ///         let res = get_categories(client);
 ///         println!("{:#?}", res);
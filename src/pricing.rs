@@ -0,0 +1,254 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::Requests,
+    model::{
+        order::OrderPayload,
+        price::Price,
+        user::{Balance, User},
+    },
+    user,
+};
+
+/// How `Price.price` is charged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PriceFormat {
+    PerView,
+    PerThousandViews,
+    /// Ordered views are consumed as viewer-hours
+    PerViewerHour,
+    /// Unrecognized format, charged per view
+    Other(String),
+}
+
+impl PriceFormat {
+    /// Parse a `Price.format` value
+    ///
+    /// ```rust
+    /// use reydenx::pricing::PriceFormat;
+    ///
+    /// assert_eq!(PriceFormat::parse("per_1000"), PriceFormat::PerThousandViews);
+    /// assert_eq!(PriceFormat::parse("Hour"), PriceFormat::PerViewerHour);
+    /// ```
+    pub fn parse(format: &str) -> Self {
+        match format.trim().to_lowercase().as_str() {
+            "view" | "views" | "per_view" => PriceFormat::PerView,
+            "1000" | "1000_views" | "per_1000" | "thousand" | "cpm" => {
+                PriceFormat::PerThousandViews
+            }
+            "hour" | "hours" | "per_hour" | "viewer_hour" | "viewer_hours" => {
+                PriceFormat::PerViewerHour
+            }
+            _ => PriceFormat::Other(format.to_string()),
+        }
+    }
+
+    /// Number of views `Price.price` is charged for
+    pub fn views_per_unit(&self) -> f64 {
+        match self {
+            PriceFormat::PerThousandViews => 1000.0,
+            _ => 1.0,
+        }
+    }
+}
+
+impl Display for PriceFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceFormat::PerView => write!(f, "per view"),
+            PriceFormat::PerThousandViews => write!(f, "per 1000 views"),
+            PriceFormat::PerViewerHour => write!(f, "per viewer-hour"),
+            PriceFormat::Other(format) => write!(f, "{}", format),
+        }
+    }
+}
+
+/// Price of a single view (or viewer-hour) before discount
+pub fn unit_price(price: &Price) -> f64 {
+    price.price / PriceFormat::parse(&price.format).views_per_unit()
+}
+
+/// Cost breakdown of an order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Estimate {
+    pub price_id: u32,
+    pub format: PriceFormat,
+    pub views: u32,
+    pub viewers: u32,
+    pub base: f64,
+    pub discount_percent: u32,
+    pub discount: f64,
+    pub total: f64,
+    /// `Balance.currency`, unknown until `with_balance` is applied
+    pub currency: Option<String>,
+    /// Only for prices charged per viewer-hour
+    pub cost_per_viewer_hour: Option<f64>,
+}
+
+impl Estimate {
+    pub fn with_balance(mut self, balance: &Balance) -> Self {
+        self.currency = Some(balance.currency.clone());
+        self
+    }
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let currency = self.currency.as_deref().unwrap_or("");
+        writeln!(f, "Price #{} ({})", self.price_id, self.format)?;
+        writeln!(f, "Views: {}, viewers: {}", self.views, self.viewers)?;
+        writeln!(f, "Base: {:.2} {}", self.base, currency)?;
+        writeln!(
+            f,
+            "Discount: {:.2} {} ({}%)",
+            self.discount, currency, self.discount_percent
+        )?;
+        write!(f, "Total: {:.2} {}", self.total, currency)?;
+        if let Some(cost) = self.cost_per_viewer_hour {
+            write!(f, "\nPer viewer-hour: {:.4} {}", cost, currency)?;
+        }
+        Ok(())
+    }
+}
+
+/// Base cost and discount of `views` at `discount_percent`, priced by `Price.format`
+pub fn cost(price: &Price, views: u32, discount_percent: u32) -> (f64, f64) {
+    let base = unit_price(price) * views as f64;
+    (base, base * discount_percent.min(100) as f64 / 100.0)
+}
+
+/// Estimate the cost of an order
+///
+/// ```rust
+/// use reydenx::{
+///     model::{
+///         order::{SmoothGain, TwitchPayload},
+///         price::{MinMaxStep, Price},
+///         user::User,
+///     },
+///     pricing::estimate,
+/// };
+///
+/// let price = Price {
+///     id: 1,
+///     name: String::from("Basic"),
+///     format: String::from("views"),
+///     price: 0.5,
+///     description: String::new(),
+///     views: MinMaxStep { min: 100, max: 100000, step: 100 },
+///     online_viewers: MinMaxStep { min: 10, max: 1000, step: 10 },
+///     category_id: 1,
+/// };
+/// let payload = TwitchPayload {
+///     price_id: 1,
+///     number_of_views: 1000,
+///     number_of_viewers: 100,
+///     launch_mode: String::from("auto"),
+///     smooth_gain: SmoothGain { enabled: false, minutes: 0 },
+///     delay_time: 0,
+///     twitch_id: 123456789,
+///     fixed_allocation: 0,
+///     on_overflow: false,
+/// };
+/// let user = User {
+///     id: 1,
+///     username: String::from("streamer"),
+///     date_joined: String::new(),
+///     email: String::new(),
+///     is_active: true,
+///     is_blocked: false,
+///     has_image: false,
+///     image_url: String::new(),
+///     currency_id: 1,
+///     discount_value: 10,
+///     is_reseller: false,
+///     twitch_id: 0,
+///     twitch_login: String::new(),
+/// };
+///
+/// let res = estimate(&price, &payload, &user);
+/// assert_eq!(res.base, 500.0);
+/// assert_eq!(res.discount, 50.0);
+/// assert_eq!(res.total, 450.0);
+/// ```
+pub fn estimate(price: &Price, payload: &impl OrderPayload, user: &User) -> Estimate {
+    let format = PriceFormat::parse(&price.format);
+    let views = payload.number_of_views();
+    let discount_percent = user.discount_value.min(100);
+
+    let (base, discount) = cost(price, views, discount_percent);
+    let total = base - discount;
+    let cost_per_viewer_hour = match format {
+        PriceFormat::PerViewerHour if views > 0 => Some(total / views as f64),
+        _ => None,
+    };
+
+    Estimate {
+        price_id: price.id,
+        format,
+        views,
+        viewers: payload.number_of_viewers(),
+        base,
+        discount_percent,
+        discount,
+        total,
+        currency: None,
+        cost_per_viewer_hour,
+    }
+}
+
+/// Estimate the cost of an order with the current user discount and balance currency
+///
+/// ```rust,no_run
+/// use reydenx::{
+///     client::{Auth, Client},
+///     model::{
+///         order::{SmoothGain, YouTubePayload},
+///         platform::Platform,
+///     },
+///     prices::get_prices,
+///     pricing::quote,
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         let price = get_prices(client, Platform::YouTube).unwrap().result.remove(0);
+///         let res = quote(
+///             client,
+///             &price,
+///             &YouTubePayload {
+///                 price_id: price.id,
+///                 number_of_views: 1000,
+///                 number_of_viewers: 100,
+///                 launch_mode: String::from("auto"),
+///                 smooth_gain: SmoothGain {
+///                     enabled: false,
+///                     minutes: 0,
+///                 },
+///                 delay_time: 0,
+///                 channel_url: String::from(
+///                     "https://www.youtube.com/channel/UCtI0Hodo5o5dUb67FeUjDeA",
+///                 ),
+///                 fixed_allocation: 0,
+///                 on_overflow: false,
+///             },
+///         );
+///         println!("{:#?}", res);
+///     }
+/// }
+/// ```
+pub fn quote(
+    c: &impl Requests,
+    price: &Price,
+    payload: &impl OrderPayload,
+) -> Result<Estimate, Box<dyn Error>> {
+    let account = user::account(c)?;
+    let balance = user::balance(c)?;
+    Ok(estimate(price, payload, &account).with_balance(&balance))
+}
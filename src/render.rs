@@ -0,0 +1,275 @@
+use std::{cmp::Ordering, collections::BTreeMap, error::Error, io::Write, str::FromStr};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    export::{cell, Exporter, Format, Record},
+    model::error::ValueError,
+    notify,
+};
+
+/// How records are printed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// Aligned columns with a header
+    Table,
+    /// Array of objects
+    Json,
+    /// One object per line
+    JsonLines,
+    Csv,
+    /// One line per record, `{column}` placeholders are replaced with values
+    Template(String),
+}
+
+impl FromStr for Output {
+    type Err = ValueError;
+
+    /// `table`, `json`, `jsonl`, `csv` or `template=TEXT`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(template) = s.strip_prefix("template=") {
+            return Ok(Output::Template(template.to_string()));
+        }
+        match s.to_lowercase().as_str() {
+            "table" => Ok(Output::Table),
+            "json" => Ok(Output::Json),
+            "jsonl" | "jsonlines" | "ndjson" => Ok(Output::JsonLines),
+            "csv" => Ok(Output::Csv),
+            _ => Err(ValueError {
+                message: format!(
+                    "Unknown output {:?}, expected table, json, jsonl, csv or template=TEXT",
+                    s
+                ),
+            }),
+        }
+    }
+}
+
+/// Renders any `Record` with column selection and sorting
+///
+/// ```rust
+/// use reydenx::{
+///     model::traffic::Traffic,
+///     render::{Output, Renderer},
+/// };
+///
+/// let traffic = vec![
+///     Traffic { code: String::from("DE"), quantity: 7 },
+///     Traffic { code: String::from("RU"), quantity: 120 },
+/// ];
+/// let renderer = Renderer::<Traffic>::new().sort_by("quantity", true).unwrap();
+///
+/// let table = renderer.render(&traffic, &Output::Table).unwrap();
+/// assert_eq!(table, "code  quantity\nRU         120\nDE           7\n");
+///
+/// let lines = renderer
+///     .render(&traffic, &Output::Template(String::from("{code}: {quantity}")))
+///     .unwrap();
+/// assert_eq!(lines, "RU: 120\nDE: 7\n");
+///
+/// let json = Renderer::<Traffic>::new().select(&["code"]).unwrap().render(&traffic, &Output::Json);
+/// assert_eq!(json.unwrap(), "[{\"code\":\"DE\"},{\"code\":\"RU\"}]\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Renderer<R: Record> {
+    columns: Vec<usize>,
+    sort: Option<(usize, bool)>,
+    record: std::marker::PhantomData<R>,
+}
+
+impl<R: Record> Default for Renderer<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Record> Renderer<R> {
+    /// All columns in record order
+    pub fn new() -> Self {
+        Self {
+            columns: (0..R::columns().len()).collect(),
+            sort: None,
+            record: std::marker::PhantomData,
+        }
+    }
+
+    /// Only the given columns, in the given order
+    pub fn select(mut self, names: &[&str]) -> Result<Self, ValueError> {
+        self.columns = names
+            .iter()
+            .map(|name| column(R::columns(), name))
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// Sort by a column, which does not have to be selected
+    pub fn sort_by(mut self, name: &str, descending: bool) -> Result<Self, ValueError> {
+        self.sort = Some((column(R::columns(), name)?, descending));
+        Ok(self)
+    }
+
+    fn rows(&self, records: &[R]) -> Vec<Vec<Value>> {
+        let mut rows: Vec<Vec<Value>> = records.iter().map(|r| r.values()).collect();
+        if let Some((i, descending)) = self.sort {
+            rows.sort_by(|a, b| {
+                let order = compare(&a[i], &b[i]);
+                if descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+        }
+        rows
+    }
+
+    pub fn render(&self, records: &[R], output: &Output) -> Result<String, Box<dyn Error>> {
+        let mut out = Vec::new();
+        self.write(&mut out, records, output)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    pub fn write(
+        &self,
+        mut writer: impl Write,
+        records: &[R],
+        output: &Output,
+    ) -> Result<(), Box<dyn Error>> {
+        let names = R::columns();
+        let rows = self.rows(records);
+        match output {
+            Output::Table => {
+                let cells: Vec<Vec<String>> = rows
+                    .iter()
+                    .map(|row| self.columns.iter().map(|i| cell(&row[*i])).collect())
+                    .collect();
+                let widths: Vec<usize> = self
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(n, i)| {
+                        cells
+                            .iter()
+                            .map(|r| r[n].chars().count())
+                            .chain([names[*i].len()])
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                let header: Vec<String> = self
+                    .columns
+                    .iter()
+                    .zip(&widths)
+                    .map(|(i, w)| format!("{:<w$}", names[*i], w = w))
+                    .collect();
+                writeln!(writer, "{}", header.join("  ").trim_end())?;
+                for (row, values) in cells.iter().zip(&rows) {
+                    let line: Vec<String> = row
+                        .iter()
+                        .zip(&self.columns)
+                        .zip(&widths)
+                        .map(|((text, i), w)| match values[*i] {
+                            Value::Number(_) => format!("{:>w$}", text, w = w),
+                            _ => format!("{:<w$}", text, w = w),
+                        })
+                        .collect();
+                    writeln!(writer, "{}", line.join("  ").trim_end())?;
+                }
+            }
+            Output::Json => {
+                let objects: Vec<Value> = rows
+                    .iter()
+                    .map(|row| Value::Object(self.object(row)))
+                    .collect();
+                serde_json::to_writer(&mut writer, &objects)?;
+                writeln!(writer)?;
+            }
+            Output::JsonLines | Output::Csv => {
+                let format = match output {
+                    Output::Csv => Format::Csv,
+                    _ => Format::JsonLines,
+                };
+                let selected: Vec<&str> = self.columns.iter().map(|i| names[*i]).collect();
+                let mut exporter =
+                    Exporter::<_, Row<R>>::new(&mut writer, format).select(&selected)?;
+                for values in rows {
+                    exporter.write(&Row {
+                        values,
+                        record: std::marker::PhantomData,
+                    })?;
+                }
+                exporter.finish()?;
+            }
+            Output::Template(template) => {
+                for row in &rows {
+                    let vars: BTreeMap<&'static str, String> =
+                        names.iter().zip(row).map(|(n, v)| (*n, cell(v))).collect();
+                    writeln!(writer, "{}", notify::render(template, &vars))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn object(&self, row: &[Value]) -> Map<String, Value> {
+        self.columns
+            .iter()
+            .map(|i| (R::columns()[*i].to_string(), row[*i].clone()))
+            .collect()
+    }
+}
+
+/// Sorted values of a record, so the exporter writes them in sorted order
+struct Row<R: Record> {
+    values: Vec<Value>,
+    record: std::marker::PhantomData<R>,
+}
+
+impl<R: Record> Record for Row<R> {
+    fn columns() -> &'static [&'static str] {
+        R::columns()
+    }
+
+    fn values(&self) -> Vec<Value> {
+        self.values.clone()
+    }
+}
+
+fn column(columns: &[&str], name: &str) -> Result<usize, ValueError> {
+    columns
+        .iter()
+        .position(|c| *c == name)
+        .ok_or_else(|| ValueError {
+            message: format!(
+                "Unknown column {:?}, available: {}",
+                name,
+                columns.join(", ")
+            ),
+        })
+}
+
+/// Nulls first, numbers by value, everything else by text
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .unwrap_or(0.0)
+            .total_cmp(&y.as_f64().unwrap_or(0.0)),
+        _ => cell(a).cmp(&cell(b)),
+    }
+}
+
+/// Render records with all columns in record order
+///
+/// ```rust
+/// use reydenx::{model::order::SiteStats, render::{render, Output}};
+///
+/// let sites = vec![SiteStats { domain: String::from("a.com"), views: 10, clicks: 1, ctr: 10.0 }];
+/// assert_eq!(render(&sites, &Output::Csv).unwrap(), "domain,views,clicks,ctr\na.com,10,1,10.0\n");
+/// ```
+pub fn render<R: Record>(records: &[R], output: &Output) -> Result<String, Box<dyn Error>> {
+    Renderer::<R>::new().render(records, output)
+}
@@ -0,0 +1,649 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action,
+    analytics::parse_time,
+    client::Requests,
+    lifecycle::OrderState,
+    model::{
+        order::{OnlineStats, Order},
+        result::{ActionResult, Pages},
+        user::Balance,
+    },
+    orders, user,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Percent
+    Ctr,
+    Views,
+    Clicks,
+    ViewsRemaining,
+    ViewsRemainingPercent,
+    InFact,
+    InSettings,
+    /// Delivered viewers as a percent of ordered viewers
+    DeliveryPercent,
+    OnlineLimit,
+    Balance,
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Metric::Ctr => "ctr",
+            Metric::Views => "views",
+            Metric::Clicks => "clicks",
+            Metric::ViewsRemaining => "views_remaining",
+            Metric::ViewsRemainingPercent => "views_remaining_percent",
+            Metric::InFact => "in_fact",
+            Metric::InSettings => "in_settings",
+            Metric::DeliveryPercent => "delivery_percent",
+            Metric::OnlineLimit => "online_limit",
+            Metric::Balance => "balance",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op {
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "==")]
+    Eq,
+}
+
+impl Op {
+    pub fn holds(&self, left: f64, right: f64) -> bool {
+        match self {
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Eq => left == right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub metric: Metric,
+    pub op: Op,
+    pub value: f64,
+    /// Minutes the condition must hold before the rule fires
+    #[serde(default)]
+    pub for_minutes: u32,
+}
+
+/// Action from `action.rs` taken when a rule fires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    Run,
+    Stop,
+    Cancel,
+    AddViews { value: u32 },
+    ChangeOnline { value: u32 },
+    ChangeIncreaseTime { value: u32 },
+    EnableIncrease { value: u32 },
+    DisableIncrease,
+}
+
+impl RuleAction {
+    pub fn execute(
+        &self,
+        c: &impl Requests,
+        order_id: u32,
+    ) -> Result<ActionResult, Box<dyn Error>> {
+        match self {
+            RuleAction::Run => action::run(c, order_id),
+            RuleAction::Stop => action::stop(c, order_id),
+            RuleAction::Cancel => action::cancel(c, order_id),
+            RuleAction::AddViews { value } => action::add_views(c, order_id, *value),
+            RuleAction::ChangeOnline { value } => action::change_online(c, order_id, *value),
+            RuleAction::ChangeIncreaseTime { value } => {
+                action::change_increase_time(c, order_id, *value)
+            }
+            RuleAction::EnableIncrease { value } => {
+                action::enable_increase_of_viewers(c, order_id, *value)
+            }
+            RuleAction::DisableIncrease => action::disable_increase_of_viewers(c, order_id),
+        }
+    }
+}
+
+impl Display for RuleAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleAction::Run => write!(f, "run"),
+            RuleAction::Stop => write!(f, "stop"),
+            RuleAction::Cancel => write!(f, "cancel"),
+            RuleAction::AddViews { value } => write!(f, "add {} views", value),
+            RuleAction::ChangeOnline { value } => write!(f, "change online to {}", value),
+            RuleAction::ChangeIncreaseTime { value } => {
+                write!(f, "change increase time to {}", value)
+            }
+            RuleAction::EnableIncrease { value } => write!(f, "enable increase of {}", value),
+            RuleAction::DisableIncrease => write!(f, "disable increase"),
+        }
+    }
+}
+
+fn default_cooldown() -> u32 {
+    30
+}
+
+/// Fires `action` on an order once every condition holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    pub when: Vec<Condition>,
+    pub action: RuleAction,
+    /// Minutes before the rule may fire again for the same order
+    #[serde(default = "default_cooldown")]
+    pub cooldown_minutes: u32,
+    /// Record what would happen without calling the API
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Orders the rule applies to, every order when empty
+    #[serde(default)]
+    pub orders: Vec<u32>,
+}
+
+impl Rule {
+    pub fn new(name: &str, action: RuleAction) -> Self {
+        Self {
+            name: name.to_string(),
+            when: Vec::new(),
+            action,
+            cooldown_minutes: default_cooldown(),
+            dry_run: false,
+            orders: Vec::new(),
+        }
+    }
+
+    pub fn when(mut self, metric: Metric, op: Op, value: f64) -> Self {
+        self.when.push(Condition {
+            metric,
+            op,
+            value,
+            for_minutes: 0,
+        });
+        self
+    }
+
+    /// Like `when`, the condition must hold for `minutes`
+    pub fn when_for(mut self, metric: Metric, op: Op, value: f64, minutes: u32) -> Self {
+        self.when.push(Condition {
+            metric,
+            op,
+            value,
+            for_minutes: minutes,
+        });
+        self
+    }
+
+    pub fn cooldown(mut self, minutes: u32) -> Self {
+        self.cooldown_minutes = minutes;
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn for_orders(mut self, orders: &[u32]) -> Self {
+        self.orders = orders.to_vec();
+        self
+    }
+}
+
+/// Rules as read from TOML, one `[[rule]]` table each
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// ```rust
+    /// use reydenx::rules::{Metric, RuleAction, RuleSet};
+    ///
+    /// let rules = RuleSet::from_toml(r#"
+    ///     [[rule]]
+    ///     name = "low ctr"
+    ///     action = { type = "stop" }
+    ///     cooldown_minutes = 60
+    ///     when = [{ metric = "ctr", op = "<", value = 0.5, for_minutes = 30 }]
+    ///
+    ///     [[rule]]
+    ///     name = "top up views"
+    ///     action = { type = "add_views", value = 1000 }
+    ///     dry_run = true
+    ///     when = [{ metric = "views_remaining_percent", op = "<", value = 10 }]
+    /// "#)
+    /// .unwrap();
+    /// assert_eq!(rules.rules[0].when[0].metric, Metric::Ctr);
+    /// assert_eq!(rules.rules[1].action, RuleAction::AddViews { value: 1000 });
+    /// assert_eq!(rules.rules[1].cooldown_minutes, 30);
+    /// ```
+    pub fn from_toml(text: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str(text)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+}
+
+/// Data about one order that rules are evaluated against
+#[derive(Debug, Clone)]
+pub struct Facts {
+    pub order: Order,
+    /// Latest online sample
+    pub online: Option<OnlineStats>,
+    pub balance: Option<Balance>,
+}
+
+impl Facts {
+    pub fn new(order: Order, online: &[OnlineStats], balance: Option<Balance>) -> Self {
+        let online = online
+            .iter()
+            .filter_map(|s| parse_time(&s.created_at).map(|t| (t, s)))
+            .max_by_key(|(t, _)| *t)
+            .map(|(_, s)| s.clone());
+        Self {
+            order,
+            online,
+            balance,
+        }
+    }
+
+    /// None when the data for the metric is missing
+    pub fn value(&self, metric: Metric) -> Option<f64> {
+        let statistics = self.order.statistics.as_ref();
+        let ordered = self.order.ordered_view_qty as f64;
+        match metric {
+            Metric::Ctr => statistics.map(|s| s.ctr),
+            Metric::Views => statistics.map(|s| s.views as f64),
+            Metric::Clicks => statistics.map(|s| s.clicks as f64),
+            Metric::ViewsRemaining => statistics.map(|s| (ordered - s.views as f64).max(0.0)),
+            Metric::ViewsRemainingPercent => statistics
+                .filter(|_| ordered > 0.0)
+                .map(|s| (ordered - s.views as f64).max(0.0) / ordered * 100.0),
+            Metric::InFact => self.online.as_ref().map(|s| s.in_fact),
+            Metric::InSettings => self.online.as_ref().map(|s| s.in_settings),
+            Metric::DeliveryPercent => self
+                .online
+                .as_ref()
+                .filter(|s| s.in_settings > 0.0)
+                .map(|s| s.in_fact / s.in_settings * 100.0),
+            Metric::OnlineLimit => Some(self.order.online_users_limit as f64),
+            Metric::Balance => self.balance.as_ref().map(|b| b.amount as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AuditOutcome {
+    Executed { task_id: String },
+    DryRun,
+    Failed { message: String },
+}
+
+/// Record of a rule firing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    pub rule: String,
+    pub order_id: u32,
+    pub action: RuleAction,
+    /// Values of the rule metrics when it fired
+    pub values: BTreeMap<String, f64>,
+    pub outcome: AuditOutcome,
+}
+
+impl Display for AuditEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        write!(
+            f,
+            "{} rule {:?} order {}: {} ({}) -> ",
+            self.at.to_rfc3339(),
+            self.rule,
+            self.order_id,
+            self.action,
+            values.join(", ")
+        )?;
+        match &self.outcome {
+            AuditOutcome::Executed { task_id } => write!(f, "executed, task {}", task_id),
+            AuditOutcome::DryRun => write!(f, "dry run"),
+            AuditOutcome::Failed { message } => write!(f, "failed: {}", message),
+        }
+    }
+}
+
+/// Evaluates rules against orders and runs their actions
+///
+/// ```rust
+/// use chrono::{Duration, TimeZone, Utc};
+/// use reydenx::{
+///     client::Client,
+///     model::order::Order,
+///     rules::{AuditOutcome, Facts, Metric, Op, Rule, RuleAction, RuleEngine},
+/// };
+///
+/// let order: Order = serde_json::from_value(serde_json::json!({
+///     "id": 12345, "created_at": "", "updated_at": "", "uuid": "", "status": "active",
+///     "ordered_view_qty": 1000, "price_per_view": 0.5, "is_autostart": false,
+///     "online_users_limit": 50, "platform": "twitch", "content_type": "stream",
+///     "content_classification_labels": null,
+///     "parameters": {
+///         "launch_mode": "auto", "work_mode": "", "delay": false, "delay_time": 0,
+///         "even_distribution": false, "even_distribution_time": 0
+///     },
+///     "statistics": {
+///         "active_time_in_seconds": 600, "views": 400, "clicks": 1, "ctr": 0.25,
+///         "average": { "online": { "in_settings": 50.0, "in_fact": 48.0 }, "session_in_seconds": 60.0 }
+///     }
+/// }))
+/// .unwrap();
+/// let facts = Facts::new(order, &[], None);
+///
+/// let rule = Rule::new("low ctr", RuleAction::Stop)
+///     .when_for(Metric::Ctr, Op::Lt, 0.5, 30)
+///     .dry_run(true);
+/// let mut engine = RuleEngine::new(vec![rule]);
+/// let client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///
+/// let start = Utc.with_ymd_and_hms(2024, 3, 1, 18, 0, 0).unwrap();
+/// assert!(engine.apply(&client, &facts, start).unwrap().is_empty());
+/// let fired = engine.apply(&client, &facts, start + Duration::minutes(30)).unwrap();
+/// assert_eq!(fired[0].outcome, AuditOutcome::DryRun);
+/// // cooling down
+/// assert!(engine.apply(&client, &facts, start + Duration::minutes(40)).unwrap().is_empty());
+/// assert_eq!(engine.audit().len(), 1);
+/// ```
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+    /// When a condition of a rule started holding for an order, by rule, order and condition
+    since: HashMap<(usize, u32, usize), DateTime<Utc>>,
+    fired: HashMap<(usize, u32), DateTime<Utc>>,
+    audit: Vec<AuditEntry>,
+    audit_file: Option<PathBuf>,
+    on_error: ErrorHandler,
+}
+
+type ErrorHandler = Box<dyn Fn(u32, &dyn Error) + Send + Sync>;
+
+impl fmt::Debug for RuleEngine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuleEngine")
+            .field("rules", &self.rules)
+            .field("since", &self.since)
+            .field("fired", &self.fired)
+            .field("audit", &self.audit)
+            .field("audit_file", &self.audit_file)
+            .finish()
+    }
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            since: HashMap::new(),
+            fired: HashMap::new(),
+            audit: Vec::new(),
+            audit_file: None,
+            on_error: Box::new(|_, _| {}),
+        }
+    }
+
+    pub fn from_rule_set(rules: RuleSet) -> Self {
+        Self::new(rules.rules)
+    }
+
+    /// Also append every audit entry to a JSON Lines file
+    pub fn audit_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_file = Some(path.into());
+        self
+    }
+
+    /// Handler for orders `run_once` could not evaluate, ignored by default
+    pub fn on_error(mut self, handler: impl Fn(u32, &dyn Error) + Send + Sync + 'static) -> Self {
+        self.on_error = Box::new(handler);
+        self
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn audit(&self) -> &[AuditEntry] {
+        &self.audit
+    }
+
+    /// Indexes of the rules that fire for the order at `now`
+    fn evaluate(&mut self, facts: &Facts, now: DateTime<Utc>) -> Vec<usize> {
+        let order_id = facts.order.id;
+        let mut firing = Vec::new();
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !rule.orders.is_empty() && !rule.orders.contains(&order_id) {
+                continue;
+            }
+            let key = (i, order_id);
+            let mut ready = !rule.when.is_empty();
+            for (n, c) in rule.when.iter().enumerate() {
+                let holds = facts
+                    .value(c.metric)
+                    .is_some_and(|v| c.op.holds(v, c.value));
+                if !holds {
+                    self.since.remove(&(i, order_id, n));
+                    ready = false;
+                    continue;
+                }
+                let since = *self.since.entry((i, order_id, n)).or_insert(now);
+                if now - since < Duration::minutes(c.for_minutes as i64) {
+                    ready = false;
+                }
+            }
+            if !ready {
+                continue;
+            }
+            let cooling = self
+                .fired
+                .get(&key)
+                .is_some_and(|at| now - *at < Duration::minutes(rule.cooldown_minutes as i64));
+            if cooling {
+                continue;
+            }
+            firing.push(i);
+        }
+        firing
+    }
+
+    /// Evaluate the rules for one order and run the actions of those that fire.
+    ///
+    /// Each condition has to hold for its own `for_minutes`. Fired entries are in
+    /// `audit` even when writing them to the audit file fails.
+    ///
+    /// ```rust
+    /// use chrono::{Duration, TimeZone, Utc};
+    /// use reydenx::{
+    ///     client::Client,
+    ///     model::order::{OnlineStats, Order},
+    ///     rules::{Facts, Metric, Op, Rule, RuleAction, RuleEngine},
+    /// };
+    ///
+    /// let order: Order = serde_json::from_value(serde_json::json!({
+    ///     "id": 12345, "created_at": "", "updated_at": "", "uuid": "", "status": "active",
+    ///     "ordered_view_qty": 1000, "price_per_view": 0.5, "is_autostart": false,
+    ///     "online_users_limit": 50, "platform": "twitch", "content_type": "stream",
+    ///     "content_classification_labels": null,
+    ///     "parameters": {
+    ///         "launch_mode": "auto", "work_mode": "", "delay": false, "delay_time": 0,
+    ///         "even_distribution": false, "even_distribution_time": 0
+    ///     },
+    ///     "statistics": null
+    /// }))
+    /// .unwrap();
+    /// let facts = |in_fact| {
+    ///     let online = OnlineStats {
+    ///         created_at: String::from("2024-03-01 18:00:00"),
+    ///         in_settings: 50.0,
+    ///         in_fact,
+    ///     };
+    ///     Facts::new(order.clone(), &[online], None)
+    /// };
+    ///
+    /// let rule = Rule::new("underdelivery", RuleAction::Stop)
+    ///     .when_for(Metric::OnlineLimit, Op::Ge, 50.0, 10)
+    ///     .when(Metric::InFact, Op::Lt, 25.0)
+    ///     .dry_run(true);
+    /// let mut engine = RuleEngine::new(vec![rule]).audit_file("/nonexistent/audit.jsonl");
+    /// let client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+    ///
+    /// let start = Utc.with_ymd_and_hms(2024, 3, 1, 18, 0, 0).unwrap();
+    /// assert!(engine.apply(&client, &facts(40.0), start).unwrap().is_empty());
+    /// // the limit has held for 10 minutes, delivery only just dropped
+    /// assert!(engine.apply(&client, &facts(20.0), start + Duration::minutes(10)).is_err());
+    /// assert_eq!(engine.audit().len(), 1);
+    /// ```
+    pub fn apply(
+        &mut self,
+        c: &impl Requests,
+        facts: &Facts,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+        let mut entries = Vec::new();
+        for i in self.evaluate(facts, now) {
+            let rule = &self.rules[i];
+            let outcome = if rule.dry_run {
+                AuditOutcome::DryRun
+            } else {
+                match rule.action.execute(c, facts.order.id) {
+                    Ok(res) => AuditOutcome::Executed {
+                        task_id: res.task.id,
+                    },
+                    Err(e) => AuditOutcome::Failed {
+                        message: e.to_string(),
+                    },
+                }
+            };
+            self.fired.insert((i, facts.order.id), now);
+            entries.push(AuditEntry {
+                at: now,
+                rule: rule.name.clone(),
+                order_id: facts.order.id,
+                action: rule.action.clone(),
+                values: rule
+                    .when
+                    .iter()
+                    .filter_map(|c| facts.value(c.metric).map(|v| (c.metric.to_string(), v)))
+                    .collect(),
+                outcome,
+            });
+        }
+        self.audit.extend(entries.iter().cloned());
+        if let Some(path) = &self.audit_file {
+            if !entries.is_empty() {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                for entry in &entries {
+                    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Fetch the balance and orders that are not completed or cancelled and apply the rules
+    /// to each. Orders whose statistics can't be fetched, or whose audit entries can't be
+    /// written, are passed to `on_error` and the others are still evaluated.
+    ///
+    /// ```rust,no_run
+    /// use reydenx::{
+    ///     client::{Auth, Client},
+    ///     rules::{RuleEngine, RuleSet},
+    /// };
+    ///
+    /// fn main() {
+    ///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+    ///     if let Ok(client) = client.auth() {
+    ///         let rules = RuleSet::load("rules.toml").unwrap();
+    ///         let mut engine = RuleEngine::from_rule_set(rules)
+    ///             .audit_file("audit.jsonl")
+    ///             .on_error(|order_id, e| eprintln!("order {}: {}", order_id, e));
+    ///         loop {
+    ///             match engine.run_once(client) {
+    ///                 Ok(entries) => entries.iter().for_each(|e| println!("{}", e)),
+    ///                 Err(e) => eprintln!("{}", e),
+    ///             }
+    ///             std::thread::sleep(std::time::Duration::from_secs(60));
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn run_once(&mut self, c: &impl Requests) -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+        let needs_balance = self
+            .rules
+            .iter()
+            .any(|r| r.when.iter().any(|c| c.metric == Metric::Balance));
+        let balance = if needs_balance {
+            Some(user::balance(c)?)
+        } else {
+            None
+        };
+        let now = Utc::now();
+        let mut entries = Vec::new();
+        for page in Pages::new(|cursor| orders::all_orders(c, cursor)) {
+            for order in page? {
+                if OrderState::from_status(&order.status).is_final() {
+                    continue;
+                }
+                let order_id = order.id;
+                let online = match orders::online_stats(c, order_id) {
+                    Ok(res) => res.result,
+                    Err(e) => {
+                        (self.on_error)(order_id, e.as_ref());
+                        continue;
+                    }
+                };
+                let facts = Facts::new(order, &online, balance.clone());
+                let audited = self.audit.len();
+                match self.apply(c, &facts, now) {
+                    Ok(fired) => entries.extend(fired),
+                    Err(e) => {
+                        entries.extend(self.audit[audited..].iter().cloned());
+                        (self.on_error)(order_id, e.as_ref());
+                    }
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
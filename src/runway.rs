@@ -0,0 +1,268 @@
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::Requests,
+    forecast::{forecast, Forecast},
+    lifecycle::OrderState,
+    model::{result::Pages, user::Balance},
+    orders, user,
+};
+
+/// How long the balance lasts at the spend of the running orders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Runway {
+    pub at: DateTime<Utc>,
+    pub balance: u32,
+    pub currency: String,
+    /// Current spend of all running orders
+    pub spend_per_hour: f64,
+    /// Cost of the views the running orders still have to deliver
+    pub committed_cost: f64,
+    /// Hours until the balance runs out, none when it covers every running order
+    pub hours: Option<f64>,
+    pub runs_out_at: Option<DateTime<Utc>>,
+    pub orders: Vec<Forecast>,
+}
+
+impl Runway {
+    /// Committed cost not covered by the balance
+    pub fn shortfall(&self) -> f64 {
+        (self.committed_cost - self.balance as f64).max(0.0)
+    }
+}
+
+impl Display for Runway {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "balance {} {}, spending {:.2}/h over {} running orders, ",
+            self.balance,
+            self.currency,
+            self.spend_per_hour,
+            self.orders.len()
+        )?;
+        match self.hours {
+            Some(hours) => write!(f, "runway {:.1} h", hours),
+            None => write!(f, "balance covers all running orders"),
+        }
+    }
+}
+
+/// Project the balance against the running orders.
+///
+/// Every order spends at its forecast rate until its remaining views run
+/// out, so the total spend drops as orders finish.
+///
+/// ```rust
+/// use chrono::{Duration, TimeZone, Utc};
+/// use reydenx::{forecast::Forecast, model::user::Balance, runway::runway};
+///
+/// let at = Utc.with_ymd_and_hms(2024, 3, 1, 18, 0, 0).unwrap();
+/// let order = |order_id: u32, spend_per_hour: f64, hours_left: f64| Forecast {
+///     order_id,
+///     at,
+///     ordered_views: 0,
+///     delivered_views: 0,
+///     remaining_views: 0,
+///     views_per_hour: Some(spend_per_hour),
+///     spend_per_hour: Some(spend_per_hour),
+///     remaining_cost: spend_per_hour * hours_left,
+///     hours_left: Some(hours_left),
+///     eta: Some(at + Duration::seconds((hours_left * 3600.0) as i64)),
+/// };
+/// let balance = Balance {
+///     id: 1,
+///     amount: 500,
+///     currency_id: 1,
+///     user_id: 1,
+///     formatted_amount: 500,
+///     currency: String::from("USD"),
+/// };
+///
+/// // 300 spent within the first hour, the remaining 200 last two more hours
+/// let res = runway(&balance, vec![order(1, 200.0, 1.0), order(2, 100.0, 10.0)], at);
+/// assert_eq!(res.spend_per_hour, 300.0);
+/// assert_eq!(res.hours, Some(3.0));
+/// assert_eq!(res.shortfall(), 700.0);
+/// ```
+pub fn runway(balance: &Balance, orders: Vec<Forecast>, now: DateTime<Utc>) -> Runway {
+    let mut spending: Vec<(f64, f64)> = orders
+        .iter()
+        .filter_map(|o| match (o.spend_per_hour, o.hours_left) {
+            (Some(rate), Some(hours)) if rate > 0.0 => Some((rate, hours)),
+            _ => None,
+        })
+        .collect();
+    spending.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let spend_per_hour: f64 = spending.iter().map(|(rate, _)| rate).sum();
+    let mut left = balance.amount as f64;
+    let mut rate = spend_per_hour;
+    let mut elapsed = 0.0;
+    let mut hours = None;
+    for (order_rate, ends) in spending {
+        let cost = rate * (ends - elapsed);
+        if cost >= left {
+            hours = Some(elapsed + left / rate);
+            break;
+        }
+        left -= cost;
+        elapsed = ends;
+        rate -= order_rate;
+    }
+
+    Runway {
+        at: now,
+        balance: balance.amount,
+        currency: balance.currency.clone(),
+        spend_per_hour,
+        committed_cost: orders.iter().map(|o| o.remaining_cost).sum(),
+        hours,
+        runs_out_at: hours.map(|h| now + Duration::seconds((h * 3600.0) as i64)),
+        orders,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RunwayThresholds {
+    pub min_hours: Option<f64>,
+    pub min_balance: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RunwayAlert {
+    RunwayBelow { hours: f64, threshold: f64 },
+    BalanceBelow { amount: u32, threshold: u32 },
+}
+
+impl Display for RunwayAlert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RunwayAlert::RunwayBelow { hours, threshold } => write!(
+                f,
+                "balance runs out in {:.1} h, below {:.1} h",
+                hours, threshold
+            ),
+            RunwayAlert::BalanceBelow { amount, threshold } => {
+                write!(f, "balance {} is below {}", amount, threshold)
+            }
+        }
+    }
+}
+
+impl RunwayThresholds {
+    pub fn alerts(&self, runway: &Runway) -> Vec<RunwayAlert> {
+        let mut alerts = Vec::new();
+        if let (Some(threshold), Some(hours)) = (self.min_hours, runway.hours) {
+            if hours < threshold {
+                alerts.push(RunwayAlert::RunwayBelow { hours, threshold });
+            }
+        }
+        if let Some(threshold) = self.min_balance {
+            if runway.balance < threshold {
+                alerts.push(RunwayAlert::BalanceBelow {
+                    amount: runway.balance,
+                    threshold,
+                });
+            }
+        }
+        alerts
+    }
+}
+
+type AlertHandler = Box<dyn Fn(&RunwayAlert, &Runway) + Send + Sync>;
+
+/// Checks the balance against the running orders and reports low runway
+///
+/// ```rust,no_run
+/// use chrono::Duration;
+/// use reydenx::{
+///     client::{Auth, Client},
+///     runway::{BalanceMonitor, RunwayThresholds},
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         let monitor = BalanceMonitor::new(RunwayThresholds {
+///             min_hours: Some(2.0),
+///             min_balance: Some(1000),
+///         })
+///         .on_alert(|alert, runway| eprintln!("{}: {}", alert, runway));
+///         loop {
+///             match monitor.check(client) {
+///                 Ok(runway) => println!("{}", runway),
+///                 Err(e) => eprintln!("{}", e),
+///             }
+///             std::thread::sleep(Duration::minutes(5).to_std().unwrap());
+///         }
+///     }
+/// }
+/// ```
+pub struct BalanceMonitor {
+    thresholds: RunwayThresholds,
+    window: Duration,
+    on_alert: AlertHandler,
+}
+
+impl Debug for BalanceMonitor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BalanceMonitor")
+            .field("thresholds", &self.thresholds)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+impl BalanceMonitor {
+    /// Spend rates come from the last hour of views
+    pub fn new(thresholds: RunwayThresholds) -> Self {
+        Self {
+            thresholds,
+            window: Duration::hours(1),
+            on_alert: Box::new(|_, _| {}),
+        }
+    }
+
+    /// Window of `views_stats` used for the spend rate of each order
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Handler for alerts, ignored by default
+    pub fn on_alert(
+        mut self,
+        handler: impl Fn(&RunwayAlert, &Runway) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_alert = Box::new(handler);
+        self
+    }
+
+    /// Fetch the balance and running orders, call the handler for every alert
+    pub fn check(&self, c: &impl Requests) -> Result<Runway, Box<dyn Error>> {
+        let balance = user::balance(c)?;
+        let now = Utc::now();
+        let mut forecasts = Vec::new();
+        for page in Pages::new(|cursor| orders::all_orders(c, cursor)) {
+            for order in page? {
+                if OrderState::from_status(&order.status) != OrderState::Running {
+                    continue;
+                }
+                let views = orders::views_stats(c, order.id)?.result;
+                forecasts.push(forecast(&order, &views, self.window, now));
+            }
+        }
+        let runway = runway(&balance, forecasts, now);
+        for alert in self.thresholds.alerts(&runway) {
+            (self.on_alert)(&alert, &runway);
+        }
+        Ok(runway)
+    }
+}
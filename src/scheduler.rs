@@ -0,0 +1,599 @@
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action,
+    client::Requests,
+    model::{
+        error::ValueError,
+        order::{LaunchMode, LaunchParams},
+        result::ActionResult,
+    },
+};
+
+/// Source of the current time, replaceable in tests
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+impl<C: Clock> Clock for &C {
+    fn now(&self) -> DateTime<Utc> {
+        (*self).now()
+    }
+}
+
+/// Order action performed by a job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobAction {
+    Run,
+    Stop,
+    ChangeOnline {
+        value: u32,
+    },
+    /// `mode` is one of `auto`, `manual` or `delay`
+    ChangeLaunchMode {
+        mode: String,
+        delay_time: u8,
+    },
+}
+
+impl JobAction {
+    /// Check the launch mode of `ChangeLaunchMode`
+    pub fn validate(&self) -> Result<(), ValueError> {
+        if let JobAction::ChangeLaunchMode { mode, .. } = self {
+            mode.parse::<LaunchMode>()?;
+        }
+        Ok(())
+    }
+
+    pub fn execute(
+        &self,
+        c: &impl Requests,
+        order_id: u32,
+    ) -> Result<ActionResult, Box<dyn Error>> {
+        match self {
+            JobAction::Run => action::run(c, order_id),
+            JobAction::Stop => action::stop(c, order_id),
+            JobAction::ChangeOnline { value } => action::change_online(c, order_id, *value),
+            JobAction::ChangeLaunchMode { mode, delay_time } => action::change_launch_mode(
+                c,
+                order_id,
+                &LaunchParams {
+                    mode: mode.parse()?,
+                    delay_time: *delay_time,
+                },
+            ),
+        }
+    }
+}
+
+impl Display for JobAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JobAction::Run => write!(f, "run"),
+            JobAction::Stop => write!(f, "stop"),
+            JobAction::ChangeOnline { value } => write!(f, "change online to {}", value),
+            JobAction::ChangeLaunchMode { mode, .. } => {
+                write!(f, "change launch mode to {}", mode)
+            }
+        }
+    }
+}
+
+/// When a job runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum When {
+    /// Once
+    At { time: DateTime<Utc> },
+    /// Cron expression evaluated in `timezone`. Five fields (minute first,
+    /// day of week 0 or 7 for Sunday as in crontab) or six and seven fields
+    /// (second first, day of week 1 for Sunday) are accepted.
+    Cron {
+        expression: String,
+        timezone: String,
+    },
+}
+
+impl When {
+    /// Once at a local time of a timezone, e.g. `2024-03-01 18:00` in `Europe/Moscow`
+    ///
+    /// ```rust
+    /// use chrono::{NaiveDate, TimeZone, Utc};
+    /// use reydenx::scheduler::When;
+    ///
+    /// let local = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(18, 0, 0).unwrap();
+    /// let when = When::at_local(local, "Europe/Moscow").unwrap();
+    /// assert_eq!(when, When::At { time: Utc.with_ymd_and_hms(2024, 3, 1, 15, 0, 0).unwrap() });
+    /// ```
+    pub fn at_local(local: NaiveDateTime, timezone: &str) -> Result<Self, ValueError> {
+        let tz = timezone_of(timezone)?;
+        match tz.from_local_datetime(&local).earliest() {
+            Some(time) => Ok(When::At {
+                time: time.with_timezone(&Utc),
+            }),
+            None => Err(ValueError {
+                message: format!("{} does not exist in {}", local, timezone),
+            }),
+        }
+    }
+
+    /// Repeat on a cron expression, e.g. `30 22 * * *` for every day at 22:30.
+    /// In five fields the day of week counts from 0 or 7 for Sunday, as in crontab.
+    ///
+    /// ```rust
+    /// use chrono::{TimeZone, Utc};
+    /// use reydenx::scheduler::When;
+    ///
+    /// // Friday noon
+    /// let after = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    /// let weekdays = When::cron("0 9 * * 1-5", "UTC").unwrap();
+    /// assert_eq!(weekdays.next_after(after), Some(Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap()));
+    /// let sunday = When::cron("0 9 * * 0", "UTC").unwrap();
+    /// assert_eq!(sunday.next_after(after), Some(Utc.with_ymd_and_hms(2024, 3, 3, 9, 0, 0).unwrap()));
+    /// let weekend = When::cron("0 9 * * 6,7", "UTC").unwrap();
+    /// assert_eq!(weekend.next_after(after), Some(Utc.with_ymd_and_hms(2024, 3, 2, 9, 0, 0).unwrap()));
+    /// assert!(When::cron("0 9 * * 8", "UTC").is_err());
+    /// ```
+    pub fn cron(expression: &str, timezone: &str) -> Result<Self, ValueError> {
+        let when = When::Cron {
+            expression: expression.to_string(),
+            timezone: timezone.to_string(),
+        };
+        when.validate()?;
+        Ok(when)
+    }
+
+    fn validate(&self) -> Result<(), ValueError> {
+        if let When::Cron {
+            expression,
+            timezone,
+        } = self
+        {
+            schedule_of(expression)?;
+            timezone_of(timezone)?;
+        }
+        Ok(())
+    }
+
+    /// First time strictly after `after`
+    ///
+    /// ```rust
+    /// use chrono::{TimeZone, Utc};
+    /// use reydenx::scheduler::When;
+    ///
+    /// let when = When::cron("30 22 * * *", "Europe/Moscow").unwrap();
+    /// let after = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    /// assert_eq!(when.next_after(after), Some(Utc.with_ymd_and_hms(2024, 3, 1, 19, 30, 0).unwrap()));
+    /// ```
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            When::At { time } => (*time > after).then_some(*time),
+            When::Cron {
+                expression,
+                timezone,
+            } => {
+                let schedule = schedule_of(expression).ok()?;
+                let tz = timezone_of(timezone).ok()?;
+                schedule
+                    .after(&after.with_timezone(&tz))
+                    .next()
+                    .map(|t| t.with_timezone(&Utc))
+            }
+        }
+    }
+}
+
+fn timezone_of(timezone: &str) -> Result<Tz, ValueError> {
+    Tz::from_str(timezone).map_err(|_| ValueError {
+        message: format!("Unknown timezone {:?}", timezone),
+    })
+}
+
+fn schedule_of(expression: &str) -> Result<Schedule, ValueError> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let expression = match fields.as_slice() {
+        [minute, hour, day, month, weekday] => format!(
+            "0 {} {} {} {} {}",
+            minute,
+            hour,
+            day,
+            month,
+            weekdays(weekday)?
+        ),
+        _ => expression.to_string(),
+    };
+    Schedule::from_str(&expression).map_err(|e| ValueError {
+        message: format!("Invalid cron expression {:?}: {}", expression, e),
+    })
+}
+
+/// Day-of-week field from standard cron numbering (0 or 7 is Sunday) to the cron
+/// crate's (1 is Sunday). Names are kept as they are.
+fn weekdays(field: &str) -> Result<String, ValueError> {
+    let invalid = || ValueError {
+        message: format!("Invalid day of week {:?}", field),
+    };
+    let mut items = Vec::new();
+    for item in field.split(',') {
+        let (base, step) = match item.split_once('/') {
+            Some((base, step)) => match step.parse::<usize>() {
+                Ok(step) if step > 0 => (base, Some(step)),
+                _ => return Err(invalid()),
+            },
+            None => (item, None),
+        };
+        let number = |text: &str| text.parse::<u32>().ok().filter(|n| *n <= 7);
+        let (from, to) = match (base, base.split_once('-')) {
+            ("*", _) if step.is_none() => {
+                items.push(item.to_string());
+                continue;
+            }
+            ("*", _) => (0, 6),
+            (_, Some((from, to))) => match (number(from), number(to)) {
+                (Some(from), Some(to)) if from <= to => (from, to),
+                (None, None) => {
+                    items.push(item.to_string());
+                    continue;
+                }
+                _ => return Err(invalid()),
+            },
+            _ => match number(base) {
+                Some(from) if step.is_some() => (from, from.max(6)),
+                Some(from) => (from, from),
+                None if base.parse::<u32>().is_ok() => return Err(invalid()),
+                None => {
+                    items.push(item.to_string());
+                    continue;
+                }
+            },
+        };
+        let step = step.unwrap_or(1);
+        let days: BTreeSet<u32> = (from..=to).step_by(step).map(|d| d % 7 + 1).collect();
+        items.extend(days.iter().map(|d| d.to_string()));
+    }
+    Ok(items.join(","))
+}
+
+/// What to do with runs missed while the scheduler was not running
+///
+/// ```rust
+/// use std::error::Error;
+///
+/// use chrono::{Duration, TimeZone, Utc};
+/// use reydenx::{
+///     client::Requests,
+///     scheduler::{JobAction, ManualClock, MissedPolicy, Outcome, Scheduler, When},
+/// };
+///
+/// struct Api;
+///
+/// impl Requests for Api {
+///     fn request(&self, _: reqwest::Method, _: &str, _: Option<String>) -> Result<String, Box<dyn Error>> {
+///         Ok(String::from(
+///             r#"{"request_id": "1", "order_id": 123, "action": "run", "value": 0,
+///                 "task": {"id": "task-1", "url": "", "expires_at": ""}}"#,
+///         ))
+///     }
+///     fn get(&self, path: &str) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::GET, path, None) }
+///     fn post(&self, path: &str, p: String) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::POST, path, Some(p)) }
+///     fn delete(&self, path: &str) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::DELETE, path, None) }
+///     fn patch(&self, path: &str, p: Option<String>) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::PATCH, path, p) }
+/// }
+///
+/// let start = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+/// let clock = ManualClock::new(start);
+/// let mut scheduler = Scheduler::new(&clock);
+/// let when = || When::Cron { expression: String::from("0 0 * * * *"), timezone: String::from("UTC") };
+/// let skip = scheduler.add(123, JobAction::Run, when(), MissedPolicy::Skip).unwrap();
+/// let catch_up = scheduler.add(123, JobAction::Stop, when(), MissedPolicy::CatchUp).unwrap();
+///
+/// // down from 12:00 until 15:30, the 13:00, 14:00 and 15:00 runs were missed
+/// clock.advance(Duration::minutes(210));
+/// let runs = scheduler.tick(&Api).unwrap();
+/// assert_eq!(runs.len(), 2);
+/// assert_eq!((runs[0].job_id, &runs[0].outcome), (skip, &Outcome::Skipped));
+/// assert_eq!(runs[1].job_id, catch_up);
+/// assert_eq!(runs[1].outcome, Outcome::Done { task_id: String::from("task-1") });
+/// // both wait for 16:00 now
+/// assert_eq!(scheduler.next_run(), Some(start + Duration::hours(4)));
+///
+/// let mode = JobAction::ChangeLaunchMode { mode: String::from("later"), delay_time: 0 };
+/// assert!(scheduler.add(123, mode, when(), MissedPolicy::Skip).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedPolicy {
+    /// Drop missed runs and wait for the next one
+    Skip,
+    /// Run once for all missed runs
+    CatchUp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    pub order_id: u32,
+    pub action: JobAction,
+    pub when: When,
+    pub missed: MissedPolicy,
+    /// None once a one-off job has run
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Outcome {
+    Done { task_id: String },
+    Failed { message: String },
+    Skipped,
+}
+
+/// A due job handled by `Scheduler::tick`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobRun {
+    pub job_id: u64,
+    pub order_id: u32,
+    pub action: JobAction,
+    pub scheduled_for: DateTime<Utc>,
+    pub ran_at: DateTime<Utc>,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct State {
+    next_id: u64,
+    jobs: Vec<Job>,
+}
+
+/// Runs order actions at set times.
+///
+/// Jobs are saved to the state file after every change when one is set.
+/// A run is missed when it is due for longer than the grace period.
+///
+/// ```rust
+/// use std::{cell::RefCell, error::Error};
+///
+/// use chrono::{Duration, NaiveDate, TimeZone, Utc};
+/// use reydenx::{
+///     client::Requests,
+///     scheduler::{JobAction, ManualClock, MissedPolicy, Outcome, Scheduler, When},
+/// };
+///
+/// #[derive(Default)]
+/// struct Recorder(RefCell<Vec<String>>);
+///
+/// impl Requests for Recorder {
+///     fn request(&self, _: reqwest::Method, path: &str, _: Option<String>) -> Result<String, Box<dyn Error>> {
+///         self.0.borrow_mut().push(path.to_string());
+///         Ok(String::from(
+///             r#"{"request_id": "1", "order_id": 123, "action": "run", "value": 0,
+///                 "task": {"id": "task-1", "url": "", "expires_at": ""}}"#,
+///         ))
+///     }
+///     fn get(&self, path: &str) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::GET, path, None) }
+///     fn post(&self, path: &str, p: String) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::POST, path, Some(p)) }
+///     fn delete(&self, path: &str) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::DELETE, path, None) }
+///     fn patch(&self, path: &str, p: Option<String>) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::PATCH, path, p) }
+/// }
+///
+/// let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap());
+/// let mut scheduler = Scheduler::new(&clock);
+/// let local = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(18, 0, 0).unwrap();
+/// scheduler
+///     .add(123, JobAction::Run, When::at_local(local, "Europe/Moscow").unwrap(), MissedPolicy::Skip)
+///     .unwrap();
+/// let client = Recorder::default();
+///
+/// assert!(scheduler.tick(&client).unwrap().is_empty());
+/// clock.advance(Duration::hours(3));
+/// let runs = scheduler.tick(&client).unwrap();
+/// assert_eq!(runs[0].outcome, Outcome::Done { task_id: String::from("task-1") });
+/// assert_eq!(*client.0.borrow(), vec![String::from("/orders/123/action/run/")]);
+/// assert_eq!(scheduler.jobs()[0].next_run, None);
+/// ```
+#[derive(Debug)]
+pub struct Scheduler<C: Clock = SystemClock> {
+    clock: C,
+    state: State,
+    path: Option<PathBuf>,
+    grace: Duration,
+}
+
+impl<C: Clock> Scheduler<C> {
+    /// Scheduler without a state file, runs due for more than a minute are missed
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            state: State::default(),
+            path: None,
+            grace: Duration::minutes(1),
+        }
+    }
+
+    /// Scheduler that keeps its jobs in `path`, loading them if the file exists
+    pub fn with_state_file(clock: C, path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref().to_path_buf();
+        let state = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => State::default(),
+            Err(e) => return Err(Box::new(e)),
+        };
+        Ok(Self {
+            clock,
+            state,
+            path: Some(path),
+            grace: Duration::minutes(1),
+        })
+    }
+
+    pub fn grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.state.jobs
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = &self.path {
+            fs::write(path, serde_json::to_string_pretty(&self.state)?)?;
+        }
+        Ok(())
+    }
+
+    /// Add a job and return its id
+    pub fn add(
+        &mut self,
+        order_id: u32,
+        action: JobAction,
+        when: When,
+        missed: MissedPolicy,
+    ) -> Result<u64, Box<dyn Error>> {
+        action.validate()?;
+        when.validate()?;
+        let now = self.clock.now();
+        let next_run = match &when {
+            When::At { time } => Some(*time),
+            When::Cron { .. } => when.next_after(now),
+        };
+        self.state.next_id += 1;
+        let id = self.state.next_id;
+        self.state.jobs.push(Job {
+            id,
+            order_id,
+            action,
+            when,
+            missed,
+            next_run,
+            last_run: None,
+        });
+        self.save()?;
+        Ok(id)
+    }
+
+    /// Remove a job, returns whether it existed
+    pub fn remove(&mut self, job_id: u64) -> Result<bool, Box<dyn Error>> {
+        let before = self.state.jobs.len();
+        self.state.jobs.retain(|j| j.id != job_id);
+        let removed = self.state.jobs.len() != before;
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Earliest upcoming run of all jobs
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        self.state.jobs.iter().filter_map(|j| j.next_run).min()
+    }
+
+    /// Run every due job once. Failed actions are reported, not retried.
+    pub fn tick(&mut self, c: &impl Requests) -> Result<Vec<JobRun>, Box<dyn Error>> {
+        let now = self.clock.now();
+        let mut runs = Vec::new();
+        for job in self.state.jobs.iter_mut() {
+            let due = match job.next_run {
+                Some(due) if due <= now => due,
+                _ => continue,
+            };
+            let outcome = if now - due > self.grace && job.missed == MissedPolicy::Skip {
+                Outcome::Skipped
+            } else {
+                match job.action.execute(c, job.order_id) {
+                    Ok(res) => Outcome::Done {
+                        task_id: res.task.id,
+                    },
+                    Err(e) => Outcome::Failed {
+                        message: e.to_string(),
+                    },
+                }
+            };
+            if outcome != Outcome::Skipped {
+                job.last_run = Some(now);
+            }
+            job.next_run = job.when.next_after(now);
+            runs.push(JobRun {
+                job_id: job.id,
+                order_id: job.order_id,
+                action: job.action.clone(),
+                scheduled_for: due,
+                ran_at: now,
+                outcome,
+            });
+        }
+        if !runs.is_empty() {
+            self.save()?;
+        }
+        Ok(runs)
+    }
+
+    /// Tick every second until `stop` is set, calling `on_run` for each handled job
+    pub fn run(
+        &mut self,
+        c: &impl Requests,
+        stop: &AtomicBool,
+        on_run: impl Fn(&JobRun),
+    ) -> Result<(), Box<dyn Error>> {
+        while !stop.load(Ordering::Relaxed) {
+            self.tick(c)?.iter().for_each(&on_run);
+            thread::sleep(std::time::Duration::from_secs(1));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::order::{Order, OrderPayload, SmoothGain};
+
+/// When an order starts delivering, in minutes from the order being placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Launch {
+    /// Starts right away, the stream is assumed to be live
+    Auto,
+    /// Starts when run by hand
+    Manual {
+        after_minutes: u32,
+    },
+    Delay {
+        minutes: u32,
+    },
+}
+
+impl Launch {
+    /// Launch from a `launch_mode` value, manual orders are assumed to be run at once
+    pub fn parse(launch_mode: &str, delay_time: u32) -> Self {
+        match launch_mode.to_lowercase().as_str() {
+            "delay" => Launch::Delay {
+                minutes: delay_time,
+            },
+            "manual" => Launch::Manual { after_minutes: 0 },
+            _ => Launch::Auto,
+        }
+    }
+
+    pub fn start_minute(&self) -> u32 {
+        match self {
+            Launch::Auto => 0,
+            Launch::Manual { after_minutes } => *after_minutes,
+            Launch::Delay { minutes } => *minutes,
+        }
+    }
+}
+
+/// Order configuration to simulate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub views: u32,
+    pub viewers: u32,
+    pub launch: Launch,
+    /// Minutes to ramp from zero to `viewers`
+    pub smooth_gain_minutes: Option<u32>,
+    /// Minutes to spread the views over
+    pub even_distribution_minutes: Option<u32>,
+}
+
+impl Scenario {
+    /// Scenario of an order creation payload
+    pub fn from_payload(payload: &impl OrderPayload) -> Self {
+        Self {
+            views: payload.number_of_views(),
+            viewers: payload.number_of_viewers(),
+            launch: Launch::parse(payload.launch_mode(), payload.delay_time()),
+            smooth_gain_minutes: gain_minutes(payload.smooth_gain()),
+            even_distribution_minutes: None,
+        }
+    }
+
+    /// Scenario of an existing order, which does not report its smooth gain
+    pub fn from_order(order: &Order) -> Self {
+        let p = &order.parameters;
+        let launch = if p.delay {
+            Launch::Delay {
+                minutes: p.delay_time,
+            }
+        } else {
+            Launch::parse(&p.launch_mode, p.delay_time)
+        };
+        Self {
+            views: order.ordered_view_qty,
+            viewers: order.online_users_limit,
+            launch,
+            smooth_gain_minutes: None,
+            even_distribution_minutes: (p.even_distribution && p.even_distribution_time > 0)
+                .then_some(p.even_distribution_time),
+        }
+    }
+
+    pub fn smooth_gain(mut self, minutes: u32) -> Self {
+        self.smooth_gain_minutes = (minutes > 0).then_some(minutes);
+        self
+    }
+
+    pub fn even_distribution(mut self, minutes: u32) -> Self {
+        self.even_distribution_minutes = (minutes > 0).then_some(minutes);
+        self
+    }
+}
+
+fn gain_minutes(gain: &SmoothGain) -> Option<u32> {
+    (gain.enabled && gain.minutes > 0).then_some(gain.minutes)
+}
+
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    /// Views one viewer consumes per hour online. Tune it from past orders,
+    /// e.g. `Statistics.views` over active hours and average online.
+    pub views_per_viewer_hour: f64,
+    /// Simulation stops here even if the views are not delivered
+    pub max_minutes: u32,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            views_per_viewer_hour: 1.0,
+            max_minutes: 7 * 24 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SimPoint {
+    pub minute: u32,
+    pub viewers: u32,
+    /// Views delivered by the end of the minute
+    pub views: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Simulation {
+    pub scenario: Scenario,
+    /// One point per minute from the order being placed
+    pub points: Vec<SimPoint>,
+    pub start_minute: u32,
+    /// First minute at the highest viewer count
+    pub peak_minute: Option<u32>,
+    pub peak_viewers: u32,
+    /// Minutes until every view is delivered, none within `max_minutes`
+    pub delivered_minute: Option<u32>,
+}
+
+impl Display for Simulation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "starts at {} min, peaks at {} viewers",
+            self.start_minute, self.peak_viewers
+        )?;
+        if let Some(minute) = self.peak_minute {
+            write!(f, " at {} min", minute)?;
+        }
+        match self.delivered_minute {
+            Some(minute) => write!(
+                f,
+                ", delivered after {} min ({:.1} h)",
+                minute,
+                minute as f64 / 60.0
+            ),
+            None => write!(f, ", not delivered within the simulation"),
+        }
+    }
+}
+
+/// Expected viewers per minute and the time until full delivery
+///
+/// ```rust
+/// use reydenx::simulator::{simulate, Launch, Scenario, SimConfig};
+///
+/// let plain = Scenario {
+///     views: 300,
+///     viewers: 100,
+///     launch: Launch::Delay { minutes: 15 },
+///     smooth_gain_minutes: None,
+///     even_distribution_minutes: None,
+/// };
+/// let config = SimConfig::default();
+///
+/// let res = simulate(&plain, &config);
+/// assert_eq!(res.start_minute, 15);
+/// assert_eq!(res.delivered_minute, Some(195));
+///
+/// let smooth = simulate(&plain.clone().smooth_gain(30), &config);
+/// assert_eq!(smooth.peak_minute, Some(45));
+/// assert!(smooth.delivered_minute.unwrap() > 195);
+///
+/// // spreading 300 views over 6 hours keeps online at 50
+/// let even = simulate(&plain.clone().even_distribution(360), &config);
+/// assert_eq!(even.peak_viewers, 50);
+/// assert_eq!(even.delivered_minute, Some(375));
+/// ```
+pub fn simulate(scenario: &Scenario, config: &SimConfig) -> Simulation {
+    let start = scenario.launch.start_minute();
+    let rate = config.views_per_viewer_hour;
+    let mut cap = scenario.viewers;
+    if let Some(minutes) = scenario.even_distribution_minutes {
+        let needed = scenario.views as f64 / (minutes as f64 / 60.0) / rate;
+        cap = cap.min(needed.ceil() as u32);
+    }
+    // viewers are summed as whole viewer-minutes so the end is not lost to rounding
+    let needed = scenario.views as f64 * 60.0;
+
+    let mut points = Vec::new();
+    let mut viewer_minutes: u64 = 0;
+    let mut peak_viewers = 0;
+    let mut peak_minute = None;
+    let mut delivered_minute = None;
+    for minute in 0..config.max_minutes {
+        let viewers = if minute < start {
+            0
+        } else {
+            let since = minute - start;
+            match scenario.smooth_gain_minutes {
+                Some(gain) if since < gain => {
+                    (cap as f64 * since as f64 / gain as f64).round() as u32
+                }
+                _ => cap,
+            }
+        };
+        viewer_minutes += viewers as u64;
+        if viewers > peak_viewers {
+            peak_viewers = viewers;
+            peak_minute = Some(minute);
+        }
+        let views = (viewer_minutes as f64 * rate / 60.0).min(scenario.views as f64);
+        points.push(SimPoint {
+            minute,
+            viewers,
+            views,
+        });
+        if viewer_minutes as f64 * rate >= needed {
+            delivered_minute = Some(minute + 1);
+            break;
+        }
+    }
+
+    Simulation {
+        scenario: scenario.clone(),
+        points,
+        start_minute: start,
+        peak_minute,
+        peak_viewers,
+        delivered_minute,
+    }
+}
+
+/// Simulate several configurations side by side
+///
+/// ```rust
+/// use reydenx::simulator::{compare, Launch, Scenario, SimConfig};
+///
+/// let base = Scenario {
+///     views: 600,
+///     viewers: 200,
+///     launch: Launch::Auto,
+///     smooth_gain_minutes: None,
+///     even_distribution_minutes: None,
+/// };
+/// let results = compare(
+///     &[("instant", base.clone()), ("gain 60", base.clone().smooth_gain(60))],
+///     &SimConfig::default(),
+/// );
+/// for (name, res) in &results {
+///     println!("{}: {}", name, res);
+/// }
+/// assert!(results[0].1.delivered_minute < results[1].1.delivered_minute);
+/// ```
+pub fn compare<'a>(
+    scenarios: &[(&'a str, Scenario)],
+    config: &SimConfig,
+) -> Vec<(&'a str, Simulation)> {
+    scenarios
+        .iter()
+        .map(|(name, s)| (*name, simulate(s, config)))
+        .collect()
+}
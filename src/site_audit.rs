@@ -0,0 +1,387 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{client::Requests, model::order::SiteStats, orders};
+
+/// Robust center and spread of a metric: median and median absolute deviation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Spread {
+    pub median: f64,
+    pub mad: f64,
+}
+
+impl Spread {
+    pub fn of(values: &[f64]) -> Option<Self> {
+        let median = median(values)?;
+        let deviations: Vec<f64> = values.iter().map(|v| (v - median).abs()).collect();
+        Some(Self {
+            median,
+            mad: median_of(deviations)?,
+        })
+    }
+
+    /// Modified z-score, none when the values do not vary
+    pub fn score(&self, value: f64) -> Option<f64> {
+        (self.mad > 0.0).then(|| 0.6745 * (value - self.median) / self.mad)
+    }
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    median_of(values.to_vec())
+}
+
+fn median_of(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+/// Typical CTR and views of a site
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub ctr: Spread,
+    pub views: Spread,
+    pub sites: usize,
+}
+
+impl Baseline {
+    /// Baseline of the sites of one order
+    pub fn from_sites(sites: &[SiteStats]) -> Option<Self> {
+        let ctr: Vec<f64> = sites.iter().map(|s| s.ctr).collect();
+        let views: Vec<f64> = sites.iter().map(|s| s.views as f64).collect();
+        Some(Self {
+            ctr: Spread::of(&ctr)?,
+            views: Spread::of(&views)?,
+            sites: sites.len(),
+        })
+    }
+
+    /// Baseline across the sites of several orders
+    pub fn from_history(orders: &[Vec<SiteStats>]) -> Option<Self> {
+        let sites: Vec<SiteStats> = orders.iter().flatten().cloned().collect();
+        Self::from_sites(&sites)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Local allow and deny lists. A domain matches itself and its subdomains.
+#[derive(Debug, Clone, Default)]
+pub struct SiteRules {
+    pub allow: HashSet<String>,
+    pub deny: HashSet<String>,
+}
+
+impl SiteRules {
+    /// Read a list with one domain per line, `#` starts a comment
+    pub fn read_list(path: impl AsRef<Path>) -> Result<HashSet<String>, Box<dyn Error>> {
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .map(|l| l.split('#').next().unwrap_or("").trim().to_lowercase())
+            .filter(|l| !l.is_empty())
+            .collect())
+    }
+
+    pub fn is_allowed(&self, domain: &str) -> bool {
+        matches(&self.allow, domain)
+    }
+
+    pub fn is_denied(&self, domain: &str) -> bool {
+        matches(&self.deny, domain)
+    }
+}
+
+fn matches(list: &HashSet<String>, domain: &str) -> bool {
+    let domain = domain.to_lowercase();
+    list.iter()
+        .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)))
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// Modified z-score above which a metric is an outlier
+    pub threshold: f64,
+    /// Sites with fewer views are only checked against the deny list
+    pub min_views: i32,
+    pub rules: SiteRules,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 3.5,
+            min_views: 20,
+            rules: SiteRules::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Reason {
+    Denied,
+    HighCtr {
+        score: f64,
+        baseline: f64,
+    },
+    LowCtr {
+        score: f64,
+        baseline: f64,
+    },
+    HighViews {
+        score: f64,
+        baseline: f64,
+    },
+    /// Deviation from the historical baseline rather than from the order
+    HistoricalCtr {
+        score: f64,
+        baseline: f64,
+    },
+    HistoricalViews {
+        score: f64,
+        baseline: f64,
+    },
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Denied => write!(f, "domain is on the deny list"),
+            Reason::HighCtr { score, baseline } => {
+                write!(
+                    f,
+                    "CTR far above order median {:.2} (score {:.1})",
+                    baseline, score
+                )
+            }
+            Reason::LowCtr { score, baseline } => {
+                write!(
+                    f,
+                    "CTR far below order median {:.2} (score {:.1})",
+                    baseline, score
+                )
+            }
+            Reason::HighViews { score, baseline } => write!(
+                f,
+                "views far above order median {:.0} (score {:.1})",
+                baseline, score
+            ),
+            Reason::HistoricalCtr { score, baseline } => write!(
+                f,
+                "CTR deviates from historical median {:.2} (score {:.1})",
+                baseline, score
+            ),
+            Reason::HistoricalViews { score, baseline } => write!(
+                f,
+                "views deviate from historical median {:.0} (score {:.1})",
+                baseline, score
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub domain: String,
+    pub views: i32,
+    pub clicks: u32,
+    pub ctr: f64,
+    /// On the deny list, ranks before every score
+    pub denied: bool,
+    /// Largest absolute score of the reasons, zero when only denied
+    pub score: f64,
+    pub reasons: Vec<Reason>,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (views {}, clicks {}, CTR {:.2}): ",
+            self.domain, self.views, self.clicks, self.ctr
+        )?;
+        let reasons: Vec<String> = self.reasons.iter().map(|r| r.to_string()).collect();
+        write!(f, "{}", reasons.join("; "))
+    }
+}
+
+/// Flag sites whose CTR or views deviate from the order or the historical baseline
+///
+/// ```rust
+/// use reydenx::{
+///     model::order::SiteStats,
+///     site_audit::{audit, AuditConfig, Finding},
+/// };
+///
+/// let site = |domain: &str, views: i32, clicks: u32| SiteStats {
+///     domain: String::from(domain),
+///     views,
+///     clicks,
+///     ctr: clicks as f64 / views as f64 * 100.0,
+/// };
+/// let sites = vec![
+///     site("a.com", 1000, 10),
+///     site("b.com", 1100, 12),
+///     site("c.com", 900, 9),
+///     site("d.com", 1050, 11),
+///     site("clickfarm.io", 1000, 300),
+/// ];
+///
+/// let findings = audit(&sites, None, &AuditConfig::default());
+/// assert_eq!(findings.len(), 1);
+/// assert_eq!(findings[0].domain, "clickfarm.io");
+///
+/// let mut config = AuditConfig::default();
+/// config.rules.deny.insert(String::from("c.com"));
+/// let findings = audit(&sites, None, &config);
+/// assert_eq!(findings[0].domain, "c.com");
+/// assert!(findings[0].denied);
+///
+/// let json = serde_json::to_string(&findings[0]).unwrap();
+/// assert_eq!(serde_json::from_str::<Finding>(&json).unwrap(), findings[0]);
+/// ```
+pub fn audit(
+    sites: &[SiteStats],
+    history: Option<&Baseline>,
+    config: &AuditConfig,
+) -> Vec<Finding> {
+    let checked: Vec<SiteStats> = sites
+        .iter()
+        .filter(|s| s.views >= config.min_views)
+        .cloned()
+        .collect();
+    let order = Baseline::from_sites(&checked);
+
+    let mut findings: Vec<Finding> = Vec::new();
+    for site in sites {
+        if config.rules.is_allowed(&site.domain) {
+            continue;
+        }
+        let mut reasons = Vec::new();
+        if config.rules.is_denied(&site.domain) {
+            reasons.push(Reason::Denied);
+        }
+        if site.views >= config.min_views {
+            if let Some(b) = &order {
+                if let Some(score) = b.ctr.score(site.ctr) {
+                    if score > config.threshold {
+                        reasons.push(Reason::HighCtr {
+                            score,
+                            baseline: b.ctr.median,
+                        });
+                    } else if score < -config.threshold {
+                        reasons.push(Reason::LowCtr {
+                            score,
+                            baseline: b.ctr.median,
+                        });
+                    }
+                }
+                if let Some(score) = b.views.score(site.views as f64) {
+                    if score > config.threshold {
+                        reasons.push(Reason::HighViews {
+                            score,
+                            baseline: b.views.median,
+                        });
+                    }
+                }
+            }
+            if let Some(b) = history {
+                if let Some(score) = b.ctr.score(site.ctr) {
+                    if score.abs() > config.threshold {
+                        reasons.push(Reason::HistoricalCtr {
+                            score,
+                            baseline: b.ctr.median,
+                        });
+                    }
+                }
+                if let Some(score) = b.views.score(site.views as f64) {
+                    if score > config.threshold {
+                        reasons.push(Reason::HistoricalViews {
+                            score,
+                            baseline: b.views.median,
+                        });
+                    }
+                }
+            }
+        }
+        if reasons.is_empty() {
+            continue;
+        }
+
+        let score = reasons
+            .iter()
+            .map(|r| match r {
+                Reason::Denied => 0.0,
+                Reason::HighCtr { score, .. }
+                | Reason::LowCtr { score, .. }
+                | Reason::HighViews { score, .. }
+                | Reason::HistoricalCtr { score, .. }
+                | Reason::HistoricalViews { score, .. } => score.abs(),
+            })
+            .fold(0.0, f64::max);
+        findings.push(Finding {
+            domain: site.domain.clone(),
+            views: site.views,
+            clicks: site.clicks,
+            ctr: site.ctr,
+            denied: reasons.contains(&Reason::Denied),
+            score,
+            reasons,
+        });
+    }
+    findings.sort_by(|a, b| {
+        b.denied
+            .cmp(&a.denied)
+            .then_with(|| b.score.total_cmp(&a.score))
+    });
+    findings
+}
+
+/// Fetch the sites of an order and audit them
+///
+/// ```rust,no_run
+/// use reydenx::{
+///     client::{Auth, Client},
+///     site_audit::{fetch_audit, AuditConfig},
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         if let Ok(findings) = fetch_audit(client, 12345, None, &AuditConfig::default()) {
+///             for finding in findings {
+///                 println!("{}", finding);
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub fn fetch_audit(
+    c: &impl Requests,
+    order_id: u32,
+    history: Option<&Baseline>,
+    config: &AuditConfig,
+) -> Result<Vec<Finding>, Box<dyn Error>> {
+    let sites = orders::sites_stats(c, order_id)?.result;
+    Ok(audit(&sites, history, config))
+}
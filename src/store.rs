@@ -0,0 +1,530 @@
+use std::{error::Error, path::Path};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    client::Requests,
+    model::{
+        error::ResponseError,
+        order::{OnlineStats, Order, Payment, Statistics},
+        platform::Platform,
+        price::Price,
+        result::Pages,
+        user::Balance,
+    },
+    orders, prices, user,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS orders (
+    id INTEGER PRIMARY KEY,
+    updated_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS order_statistics (
+    order_id INTEGER NOT NULL,
+    fetched_at TEXT NOT NULL,
+    views INTEGER NOT NULL,
+    clicks INTEGER NOT NULL,
+    ctr REAL NOT NULL,
+    active_time_in_seconds INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (order_id, fetched_at)
+);
+CREATE TABLE IF NOT EXISTS online_stats (
+    order_id INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    in_settings REAL NOT NULL,
+    in_fact REAL NOT NULL,
+    PRIMARY KEY (order_id, created_at)
+);
+CREATE TABLE IF NOT EXISTS payments (
+    id INTEGER PRIMARY KEY,
+    order_id INTEGER NOT NULL,
+    payed_at TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS prices (
+    platform TEXT NOT NULL,
+    id INTEGER NOT NULL,
+    fetched_at TEXT NOT NULL,
+    price REAL NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (platform, id, fetched_at)
+);
+CREATE TABLE IF NOT EXISTS balances (
+    fetched_at TEXT PRIMARY KEY,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+";
+
+/// A stored value with the time it was fetched
+pub type Snapshot<T> = (DateTime<Utc>, T);
+
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// Walk every page of orders, the default. When false, paging stops at the first
+    /// page without changes, which misses changed orders on later pages unless the API
+    /// lists recently updated orders first.
+    pub full: bool,
+    /// Platforms whose prices are saved, none to skip prices
+    pub prices: Vec<Platform>,
+    pub balance: bool,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            full: true,
+            prices: Vec::new(),
+            balance: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub pages: usize,
+    pub orders_seen: usize,
+    pub orders_changed: usize,
+    pub online_samples: usize,
+    pub payments: usize,
+    pub prices: usize,
+    pub balance: bool,
+}
+
+/// Local SQLite history of orders, statistics, payments, prices and balance
+///
+/// ```rust,no_run
+/// use reydenx::{
+///     client::{Auth, Client},
+///     store::{Store, SyncOptions},
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         let store = Store::open("reydenx.db").unwrap();
+///         let res = store.sync(client, &SyncOptions::default());
+///         println!("{:#?}", res);
+///         println!("{:#?}", store.orders());
+///     }
+/// }
+/// ```
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Box<dyn Error>> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Box<dyn Error>> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Connection for offline queries
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// `updated_at` of the stored order
+    pub fn order_updated_at(&self, order_id: u32) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT updated_at FROM orders WHERE id = ?1",
+                params![order_id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Save the order and a statistics sample, returns false when it did not change
+    pub fn save_order(&self, order: &Order) -> Result<bool, Box<dyn Error>> {
+        if self.is_current(order)? {
+            return Ok(false);
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        self.write_order(order)?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn is_current(&self, order: &Order) -> Result<bool, Box<dyn Error>> {
+        Ok(self.order_updated_at(order.id)?.as_deref() == Some(order.updated_at.as_str()))
+    }
+
+    fn write_order(&self, order: &Order) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO orders (id, updated_at, status, platform, data)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                order.id,
+                order.updated_at,
+                order.status,
+                order.platform,
+                serde_json::to_string(order)?
+            ],
+        )?;
+        if let Some(s) = &order.statistics {
+            self.save_statistics(order.id, s)?;
+        }
+        Ok(())
+    }
+
+    fn save_statistics(&self, order_id: u32, s: &Statistics) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO order_statistics
+             (order_id, fetched_at, views, clicks, ctr, active_time_in_seconds, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                order_id,
+                Utc::now().to_rfc3339(),
+                s.views,
+                s.clicks,
+                s.ctr,
+                s.active_time_in_seconds,
+                serde_json::to_string(s)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Samples already stored are kept as they are
+    pub fn save_online_stats(
+        &self,
+        order_id: u32,
+        stats: &[OnlineStats],
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT OR IGNORE INTO online_stats (order_id, created_at, in_settings, in_fact)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        let mut added = 0;
+        for s in stats {
+            added += stmt.execute(params![order_id, s.created_at, s.in_settings, s.in_fact])?;
+        }
+        Ok(added)
+    }
+
+    pub fn save_payments(&self, order_id: u32, payments: &[Payment]) -> Result<(), Box<dyn Error>> {
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT OR REPLACE INTO payments (id, order_id, payed_at, amount, data)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for p in payments {
+            stmt.execute(params![
+                p.id,
+                order_id,
+                p.payed_at,
+                p.amount,
+                serde_json::to_string(p)?
+            ])?;
+        }
+        Ok(())
+    }
+
+    pub fn save_prices(&self, platform: Platform, prices: &[Price]) -> Result<(), Box<dyn Error>> {
+        let fetched_at = Utc::now().to_rfc3339();
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT OR REPLACE INTO prices (platform, id, fetched_at, price, data)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for p in prices {
+            stmt.execute(params![
+                platform.to_string(),
+                p.id,
+                fetched_at,
+                p.price,
+                serde_json::to_string(p)?
+            ])?;
+        }
+        Ok(())
+    }
+
+    pub fn save_balance(&self, balance: &Balance) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO balances (fetched_at, amount, currency, data)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                Utc::now().to_rfc3339(),
+                balance.amount,
+                balance.currency,
+                serde_json::to_string(balance)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn order(&self, order_id: u32) -> Result<Option<Order>, Box<dyn Error>> {
+        let data: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT data FROM orders WHERE id = ?1",
+                params![order_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Newest first
+    pub fn orders(&self) -> Result<Vec<Order>, Box<dyn Error>> {
+        self.query("SELECT data FROM orders ORDER BY id DESC", params![])
+    }
+
+    /// Statistics samples of an order in the order they were fetched
+    pub fn statistics(&self, order_id: u32) -> Result<Vec<Snapshot<Statistics>>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT fetched_at, data FROM order_statistics WHERE order_id = ?1 ORDER BY fetched_at",
+        )?;
+        let rows = stmt.query_map(params![order_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut result = Vec::new();
+        for row in rows {
+            let (at, data) = row?;
+            result.push((
+                DateTime::parse_from_rfc3339(&at)?.with_timezone(&Utc),
+                serde_json::from_str(&data)?,
+            ));
+        }
+        Ok(result)
+    }
+
+    pub fn online_stats(&self, order_id: u32) -> Result<Vec<OnlineStats>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT created_at, in_settings, in_fact FROM online_stats
+             WHERE order_id = ?1 ORDER BY created_at",
+        )?;
+        let rows = stmt.query_map(params![order_id], |row| {
+            Ok(OnlineStats {
+                created_at: row.get(0)?,
+                in_settings: row.get(1)?,
+                in_fact: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn payments(&self, order_id: u32) -> Result<Vec<Payment>, Box<dyn Error>> {
+        self.query(
+            "SELECT data FROM payments WHERE order_id = ?1 ORDER BY payed_at",
+            params![order_id],
+        )
+    }
+
+    /// Prices of the latest snapshot of the platform
+    pub fn prices(&self, platform: Platform) -> Result<Vec<Price>, Box<dyn Error>> {
+        self.query(
+            "SELECT data FROM prices WHERE platform = ?1 AND fetched_at =
+             (SELECT MAX(fetched_at) FROM prices WHERE platform = ?1) ORDER BY id",
+            params![platform.to_string()],
+        )
+    }
+
+    /// Balance snapshots, oldest first
+    pub fn balances(&self) -> Result<Vec<Snapshot<Balance>>, Box<dyn Error>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT fetched_at, data FROM balances ORDER BY fetched_at")?;
+        let rows = stmt.query_map(params![], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut result = Vec::new();
+        for row in rows {
+            let (at, data) = row?;
+            result.push((
+                DateTime::parse_from_rfc3339(&at)?.with_timezone(&Utc),
+                serde_json::from_str(&data)?,
+            ));
+        }
+        Ok(result)
+    }
+
+    fn query<T: DeserializeOwned>(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+        let mut result = Vec::new();
+        for data in rows {
+            result.push(serde_json::from_str(&data?)?);
+        }
+        Ok(result)
+    }
+
+    /// Fetch what changed since the last sync.
+    ///
+    /// Orders are walked page by page. Only orders with a new `updated_at`
+    /// get their online statistics and payments fetched. Everything of an order is
+    /// fetched before its rows are written in one transaction, the order row last,
+    /// so an order that failed is fetched again by the next sync.
+    ///
+    /// Every page is walked unless `full` is turned off, then paging stops at
+    /// the first page without changed orders.
+    ///
+    /// ```rust
+    /// use std::{cell::{Cell, RefCell}, error::Error};
+    ///
+    /// use reydenx::{
+    ///     client::Requests,
+    ///     model::error::ValueError,
+    ///     store::{Store, SyncOptions},
+    /// };
+    ///
+    /// #[derive(Default)]
+    /// struct Api {
+    ///     updated_at: RefCell<String>,
+    ///     online_down: Cell<bool>,
+    /// }
+    ///
+    /// impl Requests for Api {
+    ///     fn request(&self, _: reqwest::Method, path: &str, _: Option<String>) -> Result<String, Box<dyn Error>> {
+    ///         let result = match path {
+    ///             "/orders/" => serde_json::json!([{
+    ///                 "id": 1, "created_at": "", "updated_at": *self.updated_at.borrow(), "uuid": "",
+    ///                 "status": "active", "ordered_view_qty": 1000, "price_per_view": 0.5,
+    ///                 "is_autostart": false, "online_users_limit": 50, "platform": "twitch",
+    ///                 "content_type": "stream", "content_classification_labels": null,
+    ///                 "parameters": {
+    ///                     "launch_mode": "auto", "work_mode": "", "delay": false, "delay_time": 0,
+    ///                     "even_distribution": false, "even_distribution_time": 0
+    ///                 },
+    ///                 "statistics": null
+    ///             }]),
+    ///             "/orders/1/statistics/online/" if self.online_down.get() => {
+    ///                 return Err(Box::new(ValueError { message: String::from("unavailable") }))
+    ///             }
+    ///             "/orders/1/statistics/online/" => serde_json::json!([
+    ///                 { "created_at": *self.updated_at.borrow(), "in_settings": 50.0, "in_fact": 48.0 }
+    ///             ]),
+    ///             "/orders/1/payments/" => serde_json::json!([{
+    ///                 "id": 7, "created_at": "", "updated_at": "", "payed_at": "2024-03-01 18:00:00",
+    ///                 "amount": 500, "external_id": "", "uuid": "", "receipt": ""
+    ///             }]),
+    ///             _ => return Err(Box::new(ValueError { message: format!("unexpected {}", path) })),
+    ///         };
+    ///         Ok(serde_json::json!({
+    ///             "request_id": "1", "cached": false, "cache_expires_at": null, "cursor": null,
+    ///             "result": result
+    ///         })
+    ///         .to_string())
+    ///     }
+    ///     fn get(&self, path: &str) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::GET, path, None) }
+    ///     fn post(&self, path: &str, p: String) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::POST, path, Some(p)) }
+    ///     fn delete(&self, path: &str) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::DELETE, path, None) }
+    ///     fn patch(&self, path: &str, p: Option<String>) -> Result<String, Box<dyn Error>> { self.request(reqwest::Method::PATCH, path, p) }
+    /// }
+    ///
+    /// let store = Store::open_in_memory().unwrap();
+    /// let options = SyncOptions { balance: false, ..Default::default() };
+    /// let api = Api::default();
+    /// *api.updated_at.borrow_mut() = String::from("2024-03-01 18:00:00");
+    ///
+    /// let report = store.sync(&api, &options).unwrap();
+    /// assert_eq!((report.orders_changed, report.online_samples, report.payments), (1, 1, 1));
+    /// // nothing changed
+    /// assert_eq!(store.sync(&api, &options).unwrap().orders_changed, 0);
+    ///
+    /// // the order changed but its statistics can't be fetched: nothing is written
+    /// *api.updated_at.borrow_mut() = String::from("2024-03-01 19:00:00");
+    /// api.online_down.set(true);
+    /// assert!(store.sync(&api, &options).is_err());
+    /// assert_eq!(store.order_updated_at(1).unwrap().as_deref(), Some("2024-03-01 18:00:00"));
+    /// assert_eq!(store.online_stats(1).unwrap().len(), 1);
+    ///
+    /// // so the next sync picks it up again
+    /// api.online_down.set(false);
+    /// let report = store.sync(&api, &options).unwrap();
+    /// assert_eq!((report.orders_changed, report.online_samples), (1, 1));
+    /// assert_eq!(store.order_updated_at(1).unwrap().as_deref(), Some("2024-03-01 19:00:00"));
+    /// assert_eq!(store.payments(1).unwrap().len(), 1);
+    /// ```
+    pub fn sync(
+        &self,
+        c: &impl Requests,
+        options: &SyncOptions,
+    ) -> Result<SyncReport, Box<dyn Error>> {
+        let mut report = SyncReport::default();
+
+        if options.balance {
+            self.save_balance(&user::balance(c)?)?;
+            report.balance = true;
+        }
+        for platform in &options.prices {
+            let list = prices::get_prices(c, *platform)?.result;
+            self.save_prices(*platform, &list)?;
+            report.prices += list.len();
+        }
+
+        for page in Pages::new(|cursor| orders::all_orders(c, cursor)) {
+            let page = page?;
+            report.pages += 1;
+            let mut changed = 0;
+            for order in &page {
+                report.orders_seen += 1;
+                if self.is_current(order)? {
+                    continue;
+                }
+                changed += 1;
+                let online = orders::online_stats(c, order.id)?.result;
+                let payments = Pages::new(|cursor| orders::payments(c, order.id, cursor))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .concat();
+
+                let tx = self.conn.unchecked_transaction()?;
+                let added = self.save_online_stats(order.id, &online)?;
+                self.save_payments(order.id, &payments)?;
+                self.write_order(order)?;
+                tx.commit()?;
+                report.online_samples += added;
+                report.payments += payments.len();
+            }
+            report.orders_changed += changed;
+            if changed == 0 && !options.full {
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Order details from the API, or the stored copy when the API is rate-limiting
+    pub fn order_details(&self, c: &impl Requests, order_id: u32) -> Result<Order, Box<dyn Error>> {
+        match orders::order_details(c, order_id) {
+            Ok(res) => {
+                self.save_order(&res.result)?;
+                Ok(res.result)
+            }
+            Err(e) if is_rate_limited(e.as_ref()) => match self.order(order_id)? {
+                Some(order) => Ok(order),
+                None => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn is_rate_limited(e: &(dyn Error + 'static)) -> bool {
+    e.downcast_ref::<ResponseError>()
+        .is_some_and(|e| e.status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+}
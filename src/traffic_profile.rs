@@ -0,0 +1,197 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::Requests,
+    iso::{country_name, language_name},
+    model::traffic::Traffic,
+    traffic,
+};
+
+/// Code used for entries grouped into "other"
+pub const OTHER: &str = "other";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Dimension {
+    Country,
+    Language,
+    Device,
+}
+
+impl Dimension {
+    /// Display name of a code, the code itself when unknown
+    pub fn name(&self, code: &str) -> String {
+        if code == OTHER {
+            return String::from("Other");
+        }
+        let name = match self {
+            Dimension::Country => country_name(code).map(String::from),
+            Dimension::Language => language_name(code).map(String::from),
+            Dimension::Device => {
+                let mut chars = code.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+            }
+        };
+        name.unwrap_or_else(|| code.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Share {
+    pub code: String,
+    pub name: String,
+    pub quantity: u32,
+    pub percent: f64,
+}
+
+/// Traffic of one dimension with percentage shares, largest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Breakdown {
+    pub dimension: Dimension,
+    pub total: u32,
+    pub shares: Vec<Share>,
+}
+
+impl Breakdown {
+    /// ```rust
+    /// use reydenx::{
+    ///     model::traffic::Traffic,
+    ///     traffic_profile::{Breakdown, Dimension},
+    /// };
+    ///
+    /// let traffic = vec![
+    ///     Traffic { code: String::from("RU"), quantity: 70 },
+    ///     Traffic { code: String::from("DE"), quantity: 27 },
+    ///     Traffic { code: String::from("FR"), quantity: 2 },
+    ///     Traffic { code: String::from("IT"), quantity: 1 },
+    /// ];
+    /// let countries = Breakdown::new(Dimension::Country, &traffic).group_small(5.0);
+    ///
+    /// assert_eq!(countries.shares[1].name, "Germany");
+    /// assert_eq!(countries.shares[2].name, "Other");
+    /// assert_eq!(countries.shares[2].percent, 3.0);
+    /// ```
+    pub fn new(dimension: Dimension, traffic: &[Traffic]) -> Self {
+        let total: u32 = traffic.iter().map(|t| t.quantity).sum();
+        let mut shares: Vec<Share> = traffic
+            .iter()
+            .map(|t| Share {
+                code: t.code.clone(),
+                name: dimension.name(&t.code),
+                quantity: t.quantity,
+                percent: percent(t.quantity, total),
+            })
+            .collect();
+        shares.sort_by(|a, b| b.quantity.cmp(&a.quantity).then(a.code.cmp(&b.code)));
+        Self {
+            dimension,
+            total,
+            shares,
+        }
+    }
+
+    /// Merge entries below `min_percent` into a single "other" entry at the end
+    pub fn group_small(self, min_percent: f64) -> Self {
+        let (mut shares, small): (Vec<Share>, Vec<Share>) = self
+            .shares
+            .into_iter()
+            .partition(|s| s.percent >= min_percent && s.code != OTHER);
+        let quantity: u32 = small.iter().map(|s| s.quantity).sum();
+        if !small.is_empty() {
+            shares.push(Share {
+                code: String::from(OTHER),
+                name: self.dimension.name(OTHER),
+                quantity,
+                percent: percent(quantity, self.total),
+            });
+        }
+        Self { shares, ..self }
+    }
+}
+
+fn percent(quantity: u32, total: u32) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    quantity as f64 / total as f64 * 100.0
+}
+
+impl Display for Breakdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for share in &self.shares {
+            writeln!(
+                f,
+                "  {:<32} {:>10} {:>6.1}%",
+                share.name, share.quantity, share.percent
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Countries, languages and devices of the account traffic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrafficProfile {
+    pub countries: Breakdown,
+    pub languages: Breakdown,
+    pub devices: Breakdown,
+}
+
+impl TrafficProfile {
+    pub fn new(countries: &[Traffic], languages: &[Traffic], devices: &[Traffic]) -> Self {
+        Self {
+            countries: Breakdown::new(Dimension::Country, countries),
+            languages: Breakdown::new(Dimension::Language, languages),
+            devices: Breakdown::new(Dimension::Device, devices),
+        }
+    }
+
+    pub fn group_small(self, min_percent: f64) -> Self {
+        Self {
+            countries: self.countries.group_small(min_percent),
+            languages: self.languages.group_small(min_percent),
+            devices: self.devices.group_small(min_percent),
+        }
+    }
+}
+
+impl Display for TrafficProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Countries ({}):", self.countries.total)?;
+        write!(f, "{}", self.countries)?;
+        writeln!(f, "Languages ({}):", self.languages.total)?;
+        write!(f, "{}", self.languages)?;
+        writeln!(f, "Devices ({}):", self.devices.total)?;
+        write!(f, "{}", self.devices)
+    }
+}
+
+/// Fetch country, language and device traffic as one profile
+///
+/// ```rust,no_run
+/// use reydenx::{
+///     client::{Auth, Client},
+///     traffic_profile::fetch_profile,
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         if let Ok(profile) = fetch_profile(client) {
+///             println!("{}", profile.group_small(1.0));
+///         }
+///     }
+/// }
+/// ```
+pub fn fetch_profile(c: &impl Requests) -> Result<TrafficProfile, Box<dyn Error>> {
+    let countries = traffic::countries(c)?.result;
+    let languages = traffic::languages(c)?.result;
+    let devices = traffic::devices(c)?.result;
+    Ok(TrafficProfile::new(&countries, &languages, &devices))
+}
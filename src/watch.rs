@@ -0,0 +1,430 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::{
+    client::Requests,
+    model::{
+        order::{Order, Statistics},
+        result::Pages,
+    },
+    orders,
+};
+
+/// Change of an order between two polls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Event {
+    /// An order that was not there on the previous poll
+    OrderCreated {
+        order: Box<Order>,
+    },
+    StatusChanged {
+        order_id: u32,
+        from: String,
+        to: String,
+    },
+    /// Delivered views crossed a percentage of the ordered views
+    ViewsMilestoneReached {
+        order_id: u32,
+        percent: f64,
+        views: u32,
+        ordered_views: u32,
+    },
+    OnlineLimitChanged {
+        order_id: u32,
+        from: u32,
+        to: u32,
+    },
+    StatisticsUpdated {
+        order_id: u32,
+        previous: Option<Statistics>,
+        current: Statistics,
+    },
+    /// Polling failed, the next attempt is delayed by the backoff
+    PollFailed {
+        message: String,
+        retry_in: Duration,
+    },
+}
+
+impl Event {
+    pub fn order_id(&self) -> Option<u32> {
+        match self {
+            Event::OrderCreated { order } => Some(order.id),
+            Event::StatusChanged { order_id, .. }
+            | Event::ViewsMilestoneReached { order_id, .. }
+            | Event::OnlineLimitChanged { order_id, .. }
+            | Event::StatisticsUpdated { order_id, .. } => Some(*order_id),
+            Event::PollFailed { .. } => None,
+        }
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::OrderCreated { order } => {
+                write!(f, "order {} created ({})", order.id, order.status)
+            }
+            Event::StatusChanged { order_id, from, to } => {
+                write!(f, "order {} status {} -> {}", order_id, from, to)
+            }
+            Event::ViewsMilestoneReached {
+                order_id,
+                percent,
+                views,
+                ordered_views,
+            } => write!(
+                f,
+                "order {} reached {}% of views ({} / {})",
+                order_id, percent, views, ordered_views
+            ),
+            Event::OnlineLimitChanged { order_id, from, to } => {
+                write!(f, "order {} online limit {} -> {}", order_id, from, to)
+            }
+            Event::StatisticsUpdated {
+                order_id, current, ..
+            } => write!(
+                f,
+                "order {} statistics: {} views, {} clicks",
+                order_id, current.views, current.clicks
+            ),
+            Event::PollFailed { message, retry_in } => {
+                write!(f, "poll failed: {} (retry in {:?})", message, retry_in)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    /// Orders to watch with `order_details`, all orders with `all_orders` when empty
+    pub order_ids: Vec<u32>,
+    pub interval: Duration,
+    /// Delay after a failed poll is multiplied by `backoff` up to `max_interval`
+    pub backoff: f64,
+    pub max_interval: Duration,
+    /// Percentages of the ordered views reported once crossed
+    pub milestones: Vec<f64>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            order_ids: Vec::new(),
+            interval: Duration::from_secs(60),
+            backoff: 2.0,
+            max_interval: Duration::from_secs(15 * 60),
+            milestones: vec![25.0, 50.0, 75.0, 100.0],
+        }
+    }
+}
+
+/// Events between the previous and the current state of an order.
+/// Without a previous state the order is reported as created.
+///
+/// ```rust
+/// use reydenx::{
+///     model::order::Order,
+///     watch::{diff, Event},
+/// };
+///
+/// let order: Order = serde_json::from_value(serde_json::json!({
+///     "id": 12345, "created_at": "2024-03-01T10:00:00Z", "updated_at": "2024-03-01T10:00:00Z",
+///     "uuid": "", "status": "active", "ordered_view_qty": 1000, "price_per_view": 0.5,
+///     "is_autostart": false, "online_users_limit": 50, "platform": "twitch",
+///     "content_type": "stream", "content_classification_labels": null,
+///     "parameters": {
+///         "launch_mode": "auto", "work_mode": "", "delay": false, "delay_time": 0,
+///         "even_distribution": false, "even_distribution_time": 0
+///     },
+///     "statistics": {
+///         "active_time_in_seconds": 600, "views": 200, "clicks": 2, "ctr": 1.0,
+///         "average": { "online": { "in_settings": 50.0, "in_fact": 48.0 }, "session_in_seconds": 60.0 }
+///     }
+/// }))
+/// .unwrap();
+/// let mut current = order.clone();
+/// current.status = String::from("stopped");
+/// current.online_users_limit = 80;
+/// current.statistics.as_mut().unwrap().views = 600;
+///
+/// let events = diff(Some(&order), &current, &[25.0, 50.0, 75.0]);
+/// assert!(matches!(&events[0], Event::StatusChanged { to, .. } if to == "stopped"));
+/// assert!(matches!(events[1], Event::OnlineLimitChanged { from: 50, to: 80, .. }));
+/// assert!(matches!(events[2], Event::StatisticsUpdated { .. }));
+/// assert!(matches!(events[3], Event::ViewsMilestoneReached { percent, .. } if percent == 25.0));
+/// assert!(matches!(events[4], Event::ViewsMilestoneReached { percent, .. } if percent == 50.0));
+/// assert_eq!(events.len(), 5);
+/// ```
+pub fn diff(previous: Option<&Order>, current: &Order, milestones: &[f64]) -> Vec<Event> {
+    let previous = match previous {
+        Some(p) => p,
+        None => {
+            return vec![Event::OrderCreated {
+                order: Box::new(current.clone()),
+            }]
+        }
+    };
+
+    let mut events = Vec::new();
+    if previous.status != current.status {
+        events.push(Event::StatusChanged {
+            order_id: current.id,
+            from: previous.status.clone(),
+            to: current.status.clone(),
+        });
+    }
+    if previous.online_users_limit != current.online_users_limit {
+        events.push(Event::OnlineLimitChanged {
+            order_id: current.id,
+            from: previous.online_users_limit,
+            to: current.online_users_limit,
+        });
+    }
+
+    if let Some(s) = &current.statistics {
+        let changed = match &previous.statistics {
+            Some(p) => {
+                p.views != s.views
+                    || p.clicks != s.clicks
+                    || p.active_time_in_seconds != s.active_time_in_seconds
+            }
+            None => true,
+        };
+        if changed {
+            events.push(Event::StatisticsUpdated {
+                order_id: current.id,
+                previous: previous.statistics.clone(),
+                current: s.clone(),
+            });
+        }
+
+        let before = previous.statistics.as_ref().map_or(0, |p| p.views);
+        let ordered = current.ordered_view_qty;
+        if ordered > 0 {
+            for percent in milestones {
+                let target = (ordered as f64 * percent / 100.0).ceil() as u32;
+                if before < target && s.views >= target {
+                    events.push(Event::ViewsMilestoneReached {
+                        order_id: current.id,
+                        percent: *percent,
+                        views: s.views,
+                        ordered_views: ordered,
+                    });
+                }
+            }
+        }
+    }
+    events
+}
+
+type Handler = Box<dyn Fn(&Event) + Send + Sync>;
+
+/// Polls orders and emits the changes between polls.
+///
+/// The first poll only records the current state, later polls emit events.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use reydenx::{
+///     client::{Auth, Client},
+///     watch::{WatchConfig, Watcher},
+/// };
+///
+/// fn main() {
+///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+///     if let Ok(client) = client.auth() {
+///         let config = WatchConfig {
+///             interval: Duration::from_secs(30),
+///             ..WatchConfig::default()
+///         };
+///         let mut watcher = Watcher::new(config).on_event(|event| println!("{}", event));
+///         loop {
+///             if let Err(e) = watcher.poll_once(client) {
+///                 eprintln!("{}", e);
+///             }
+///             std::thread::sleep(Duration::from_secs(30));
+///         }
+///     }
+/// }
+/// ```
+pub struct Watcher {
+    config: WatchConfig,
+    known: HashMap<u32, Order>,
+    primed: bool,
+    handlers: Vec<Handler>,
+}
+
+impl fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watcher")
+            .field("config", &self.config)
+            .field("known", &self.known.len())
+            .field("primed", &self.primed)
+            .finish()
+    }
+}
+
+impl Watcher {
+    pub fn new(config: WatchConfig) -> Self {
+        Self {
+            config,
+            known: HashMap::new(),
+            primed: false,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Call `handler` for every event
+    pub fn on_event(mut self, handler: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        self.handlers.push(Box::new(handler));
+        self
+    }
+
+    /// Last seen state of an order
+    pub fn order(&self, order_id: u32) -> Option<&Order> {
+        self.known.get(&order_id)
+    }
+
+    fn fetch(&self, c: &impl Requests) -> Result<Vec<Order>, Box<dyn Error>> {
+        if self.config.order_ids.is_empty() {
+            let mut list = Vec::new();
+            for page in Pages::new(|cursor| orders::all_orders(c, cursor)) {
+                list.extend(page?);
+            }
+            return Ok(list);
+        }
+        self.config
+            .order_ids
+            .iter()
+            .map(|id| Ok(orders::order_details(c, *id)?.result))
+            .collect()
+    }
+
+    /// Fetch the orders once, call the handlers and return the events
+    pub fn poll_once(&mut self, c: &impl Requests) -> Result<Vec<Event>, Box<dyn Error>> {
+        let current = self.fetch(c)?;
+        let mut events = Vec::new();
+        for order in current {
+            if self.primed {
+                events.extend(diff(
+                    self.known.get(&order.id),
+                    &order,
+                    &self.config.milestones,
+                ));
+            }
+            self.known.insert(order.id, order);
+        }
+        self.primed = true;
+        self.emit(&events);
+        Ok(events)
+    }
+
+    fn emit(&self, events: &[Event]) {
+        for event in events {
+            self.handlers.iter().for_each(|h| h(event));
+        }
+    }
+
+    fn next_delay(&self, delay: Duration) -> Duration {
+        delay
+            .mul_f64(self.config.backoff.max(1.0))
+            .min(self.config.max_interval)
+    }
+
+    /// Poll every `interval` until `stop` is set, backing off after failures
+    pub fn run(&mut self, c: &impl Requests, stop: &AtomicBool) {
+        let mut delay = self.config.interval;
+        while !stop.load(Ordering::Relaxed) {
+            match self.poll_once(c) {
+                Ok(_) => delay = self.config.interval,
+                Err(e) => {
+                    delay = self.next_delay(delay);
+                    self.emit(&[Event::PollFailed {
+                        message: e.to_string(),
+                        retry_in: delay,
+                    }]);
+                }
+            }
+            sleep_until(Instant::now() + delay, || stop.load(Ordering::Relaxed));
+        }
+    }
+
+    /// Poll in a background thread and receive the events as an async stream.
+    /// Polling stops once the stream is dropped.
+    ///
+    /// ```rust,no_run
+    /// use reydenx::{
+    ///     client::{Auth, Client},
+    ///     watch::{WatchConfig, Watcher},
+    /// };
+    /// use tokio_stream::StreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut client = Client::new(String::from("EMAIL"), String::from("PASSWORD"));
+    ///     let client = tokio::task::block_in_place(|| {
+    ///         client.auth().unwrap();
+    ///         client
+    ///     });
+    ///     let mut events = Watcher::new(WatchConfig::default()).spawn(client);
+    ///     while let Some(event) = events.next().await {
+    ///         println!("{}", event);
+    ///     }
+    /// }
+    /// ```
+    pub fn spawn<C>(mut self, c: C) -> UnboundedReceiverStream<Event>
+    where
+        C: Requests + Send + 'static,
+    {
+        let (tx, rx) = mpsc::unbounded_channel();
+        thread::spawn(move || {
+            let mut delay = self.config.interval;
+            while !tx.is_closed() {
+                let events = match self.poll_once(&c) {
+                    Ok(events) => {
+                        delay = self.config.interval;
+                        events
+                    }
+                    Err(e) => {
+                        delay = self.next_delay(delay);
+                        let event = Event::PollFailed {
+                            message: e.to_string(),
+                            retry_in: delay,
+                        };
+                        self.emit(std::slice::from_ref(&event));
+                        vec![event]
+                    }
+                };
+                if events.into_iter().any(|e| tx.send(e).is_err()) {
+                    break;
+                }
+                sleep_until(Instant::now() + delay, || tx.is_closed());
+            }
+        });
+        UnboundedReceiverStream::new(rx)
+    }
+}
+
+/// Sleep in short steps so a stop request is noticed
+pub(crate) fn sleep_until(deadline: Instant, stop: impl Fn() -> bool) {
+    let step = Duration::from_millis(250);
+    loop {
+        let now = Instant::now();
+        if now >= deadline || stop() {
+            return;
+        }
+        thread::sleep(step.min(deadline - now));
+    }
+}